@@ -0,0 +1,51 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Compare two traces written by `RandTrace::write`
+//!
+//! The first draw which differs between the traces is printed. Like `diff`, the exit status is `0`
+//! if the traces match, `1` if they differ, and `2` if a trace could not be read.
+//!
+//! ```sh
+//! cargo run --example rand_trace_diff -- left.trace right.trace
+//! ```
+
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+
+use abagames_util::RandTrace;
+
+fn read_trace(path: &str) -> RandTrace {
+    let trace = File::open(path)
+        .map_err(Into::into)
+        .and_then(|file| RandTrace::read(BufReader::new(file)));
+
+    trace.unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(2);
+    })
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.len() != 2 {
+        eprintln!("usage: rand_trace_diff <left> <right>");
+        process::exit(2);
+    }
+
+    let left = read_trace(&args[0]);
+    let right = read_trace(&args[1]);
+
+    if let Some(divergence) = left.first_divergence(&right) {
+        println!("{}", divergence);
+        process::exit(1);
+    }
+
+    println!(
+        "the traces match ({} and {} draws)",
+        left.draw_count(),
+        right.draw_count(),
+    );
+}
@@ -0,0 +1,278 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Asset lookup
+//!
+//! Assets are embedded into the game, but may be replaced by files on disk. A named asset is
+//! looked for in the user's override directory, then in the data shipped within the build or
+//! install tree, and finally in the embedded assets.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Component, PathBuf};
+
+use thiserror::Error;
+
+use crate::paths::Paths;
+
+/// Errors which may occur when loading assets.
+#[derive(Debug, Error)]
+pub enum AssetsError {
+    /// An asset file exists but could not be read.
+    #[error("failed to read asset {}: {}", path.display(), source)]
+    Io {
+        /// The path to the file.
+        path: PathBuf,
+        /// The error reading the file.
+        source: io::Error,
+    },
+    /// The asset name is not a relative path within the asset directories.
+    #[error("invalid asset name: {:?}", _0)]
+    InvalidName(String),
+    /// The asset does not exist in any source.
+    #[error("asset not found: {}", _0)]
+    NotFound(String),
+}
+
+/// A convenience alias for asset results.
+pub type AssetsResult<T> = Result<T, AssetsError>;
+
+/// Where an asset was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetSource {
+    /// A file in the user's override directory.
+    Override(PathBuf),
+    /// A file in the data shipped with the game.
+    Data(PathBuf),
+    /// The data embedded into the game.
+    Embedded,
+}
+
+impl Display for AssetSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetSource::Override(path) => write!(f, "override {}", path.display()),
+            AssetSource::Data(path) => write!(f, "data {}", path.display()),
+            AssetSource::Embedded => write!(f, "embedded"),
+        }
+    }
+}
+
+/// An asset and where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset<'a> {
+    /// The contents of the asset.
+    pub data: Cow<'a, [u8]>,
+    /// Where the asset was found.
+    pub source: AssetSource,
+}
+
+impl<'a> Asset<'a> {
+    #[inline]
+    /// The contents of the asset.
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<'a> AsRef<[u8]> for Asset<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes()
+    }
+}
+
+/// Looks up assets by name.
+///
+/// Names are relative paths using `/` as a separator, such as `sounds/explosion.wav`.
+pub struct AssetResolver<'a> {
+    /// The user's override directory.
+    override_dir: PathBuf,
+    /// The directory of data shipped with the game.
+    shipped_dir: PathBuf,
+    /// The embedded assets.
+    embedded: HashMap<&'a str, &'a [u8]>,
+}
+
+impl<'a> AssetResolver<'a> {
+    /// Create a resolver searching the override and shipped data directories.
+    pub fn new(paths: &Paths) -> Self {
+        AssetResolver {
+            override_dir: paths.assets_dir.clone(),
+            shipped_dir: paths.shipped_data_dir.clone(),
+            embedded: HashMap::new(),
+        }
+    }
+
+    /// Add embedded assets to fall back to.
+    pub fn with_embedded<E>(&mut self, assets: E) -> &mut Self
+    where
+        E: IntoIterator<Item = &'a (&'a str, &'a [u8])>,
+    {
+        self.embedded.extend(assets.into_iter().copied());
+        self
+    }
+
+    /// The relative path to an asset.
+    fn relative_path(name: &str) -> AssetsResult<PathBuf> {
+        let path = name.split('/').collect::<PathBuf>();
+        // Empty segments would allow absolute paths.
+        let is_valid = name.split('/').all(|segment| !segment.is_empty())
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+
+        if is_valid {
+            Ok(path)
+        } else {
+            Err(AssetsError::InvalidName(name.into()))
+        }
+    }
+
+    /// Find where an asset would be loaded from without reading it.
+    pub fn find(&self, name: &str) -> AssetsResult<AssetSource> {
+        let relative = Self::relative_path(name)?;
+
+        let override_path = self.override_dir.join(&relative);
+        if override_path.is_file() {
+            return Ok(AssetSource::Override(override_path));
+        }
+
+        let data_path = self.shipped_dir.join(&relative);
+        if data_path.is_file() {
+            return Ok(AssetSource::Data(data_path));
+        }
+
+        self.embedded
+            .get(name)
+            .map(|_| AssetSource::Embedded)
+            .ok_or_else(|| AssetsError::NotFound(name.into()))
+    }
+
+    #[inline]
+    /// Whether an asset exists in any source.
+    pub fn contains(&self, name: &str) -> bool {
+        self.find(name).is_ok()
+    }
+
+    /// Load an asset.
+    pub fn load(&self, name: &str) -> AssetsResult<Asset<'a>> {
+        let source = self.find(name)?;
+        let data = match source {
+            AssetSource::Override(ref path) | AssetSource::Data(ref path) => {
+                let data = fs::read(path).map_err(|source| {
+                    AssetsError::Io {
+                        path: path.clone(),
+                        source,
+                    }
+                })?;
+                Cow::Owned(data)
+            },
+            AssetSource::Embedded => Cow::Borrowed(self.embedded[name]),
+        };
+
+        Ok(Asset {
+            data,
+            source,
+        })
+    }
+
+    /// Load several assets, keyed by name.
+    ///
+    /// The assets are in the same order as the names.
+    pub fn load_all<N>(&self, names: N) -> AssetsResult<Vec<(&'a str, Asset<'a>)>>
+    where
+        N: IntoIterator<Item = &'a str>,
+    {
+        names
+            .into_iter()
+            .map(|name| self.load(name).map(|asset| (name, asset)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::fs;
+
+    use crate::assets::{AssetResolver, AssetSource, AssetsError};
+    use crate::paths::Paths;
+    use crate::test_util::TempDir;
+
+    const EMBEDDED: &[(&str, &[u8])] = &[
+        ("sounds/shot.wav", b"embedded shot"),
+        ("sounds/hit.wav", b"embedded hit"),
+        ("music/title.ogg", b"embedded title"),
+    ];
+
+    #[test]
+    fn test_asset_resolver_order() {
+        let root = TempDir::new("assets");
+        let paths = Paths::for_test(root.path());
+
+        let override_path = paths.assets_dir.join("sounds").join("shot.wav");
+        let data_path = paths.shipped_data_dir.join("sounds").join("shot.wav");
+        let data_only_path = paths.shipped_data_dir.join("sounds").join("hit.wav");
+        fs::create_dir_all(override_path.parent().unwrap()).unwrap();
+        fs::create_dir_all(data_path.parent().unwrap()).unwrap();
+        fs::write(&override_path, "override shot").unwrap();
+        fs::write(&data_path, "data shot").unwrap();
+        fs::write(&data_only_path, "data hit").unwrap();
+
+        let mut resolver = AssetResolver::new(&paths);
+        resolver.with_embedded(EMBEDDED);
+
+        let shot = resolver.load("sounds/shot.wav").unwrap();
+        assert_eq!(shot.source, AssetSource::Override(override_path.clone()));
+        assert_eq!(shot.bytes(), b"override shot");
+
+        let hit = resolver.load("sounds/hit.wav").unwrap();
+        assert_eq!(hit.source, AssetSource::Data(data_only_path));
+        assert_eq!(hit.bytes(), b"data hit");
+
+        let title = resolver.load("music/title.ogg").unwrap();
+        assert_eq!(title.source, AssetSource::Embedded);
+        assert_eq!(title.data, Cow::Borrowed(&b"embedded title"[..]));
+        assert_eq!(title.source.to_string(), "embedded");
+
+        // Removing the override falls back to the data directory.
+        fs::remove_file(&override_path).unwrap();
+        assert_eq!(
+            resolver.find("sounds/shot.wav").unwrap(),
+            AssetSource::Data(data_path),
+        );
+
+        let all = resolver
+            .load_all(vec!["music/title.ogg", "sounds/hit.wav"])
+            .unwrap();
+        assert_eq!(
+            all.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["music/title.ogg", "sounds/hit.wav"],
+        );
+    }
+
+    #[test]
+    fn test_asset_resolver_errors() {
+        let root = TempDir::new("assets-errors");
+        let paths = Paths::for_test(root.path());
+        let mut resolver = AssetResolver::new(&paths);
+        resolver.with_embedded(EMBEDDED);
+
+        assert!(!resolver.contains("missing.wav"));
+        assert!(matches!(
+            resolver.load("missing.wav"),
+            Err(AssetsError::NotFound(_)),
+        ));
+
+        for name in &["", "../secret", "/etc/passwd", "sounds/../../secret"] {
+            assert!(
+                matches!(resolver.find(name), Err(AssetsError::InvalidName(_))),
+                "{:?}",
+                name,
+            );
+        }
+    }
+}
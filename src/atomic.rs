@@ -0,0 +1,130 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A counter to give each temporary file in the process a unique name.
+static TEMPORARY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The path of a new temporary file used while writing `path`.
+///
+/// The name is unique so that concurrent writers do not clobber each other's temporary files.
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    if let Some(file_name) = path.file_name() {
+        name.push(file_name);
+    }
+    name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TEMPORARY_COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+    path.with_file_name(name)
+}
+
+/// Flush a rename within a directory to disk.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Flush a rename within a directory to disk.
+///
+/// Directories cannot be opened as files on this platform; renames are durable once they return.
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Write a file atomically.
+///
+/// The contents are written to a temporary file next to the destination which then replaces it.
+/// Readers see either the old or the new contents, even if the game crashes while writing. The
+/// parent directory is created if necessary and is synced so that the new file survives a power
+/// loss.
+pub(crate) fn write_atomic<P, C>(path: P, contents: C) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let tmp_path = temporary_path(path);
+    let write = || {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    };
+
+    if let Err(err) = write() {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+
+    sync_dir(parent)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::thread;
+
+    use crate::atomic::write_atomic;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_write_atomic() {
+        let root = TempDir::new("atomic");
+        let path = root.path().join("nested").join("file.txt");
+
+        write_atomic(&path, "first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        // No temporary files are left behind.
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+
+        // Failures clean up after themselves.
+        assert!(write_atomic(root.path().join("nested"), "directory").is_err());
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_concurrent() {
+        let root = TempDir::new("atomic-concurrent");
+        let path = root.path().join("file.txt");
+
+        let contents = (0..8)
+            .map(|idx| format!("writer {}", idx).repeat(1000))
+            .collect::<Vec<_>>();
+        let writers = contents
+            .iter()
+            .cloned()
+            .map(|content| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    (0..10).for_each(|_| write_atomic(&path, &content).unwrap());
+                })
+            })
+            .collect::<Vec<_>>();
+        writers
+            .into_iter()
+            .for_each(|writer| writer.join().unwrap());
+
+        // The file holds exactly one writer's contents and no temporary files are left behind.
+        assert!(contents.contains(&fs::read_to_string(&path).unwrap()));
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,276 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Camera effects
+//!
+//! The camera tracks a target with some lag and applies screen shake and zoom pulses. Effects are
+//! triggered and advanced from `Game::step` so that they are deterministic; the resulting view
+//! matrix is applied when drawing by multiplying it onto `EncoderContext::perspective_matrix`.
+
+use cgmath::{Matrix4, Vector2, Vector3, Zero};
+
+use crate::rand::{Rand, RandBackend};
+use crate::tween::{Easing, Tween};
+
+/// Shake amplitudes below this are treated as zero.
+const SHAKE_EPSILON: f32 = 1e-3;
+
+#[derive(Debug, Clone)]
+/// A camera looking down the negative z axis.
+pub struct Camera {
+    /// The position of the camera.
+    position: Vector3<f32>,
+    /// The position the camera is moving toward.
+    target: Vector2<f32>,
+    /// The fraction of the distance to the target covered each frame.
+    follow_rate: f32,
+    /// The current shake amplitude.
+    shake: f32,
+    /// The factor applied to the shake amplitude each frame.
+    shake_decay: f32,
+    /// The current shake displacement.
+    shake_offset: Vector2<f32>,
+    /// The current zoom pulse.
+    zoom_pulse: Option<Tween<f32>>,
+}
+
+impl Camera {
+    /// Create a new camera at a position.
+    pub fn new(position: Vector3<f32>) -> Self {
+        Camera {
+            position,
+            target: position.truncate(),
+            follow_rate: 1.,
+            shake: 0.,
+            shake_decay: 0.,
+            shake_offset: Vector2::zero(),
+            zoom_pulse: None,
+        }
+    }
+
+    #[inline]
+    /// The position of the camera, without shake.
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    /// Move the camera immediately.
+    pub fn set_position(&mut self, position: Vector3<f32>) {
+        self.position = position;
+        self.target = position.truncate();
+    }
+
+    /// Set how quickly the camera follows its target.
+    ///
+    /// The camera covers `rate` of the remaining distance each frame; `1` follows without lag.
+    pub fn set_follow_rate(&mut self, rate: f32) {
+        self.follow_rate = rate;
+    }
+
+    #[inline]
+    /// Set the point for the camera to follow.
+    pub fn follow(&mut self, target: Vector2<f32>) {
+        self.target = target;
+    }
+
+    /// Start shaking the camera.
+    ///
+    /// The shake moves the view by up to `amplitude` in each direction and the amplitude is
+    /// multiplied by `decay` each frame. A weaker shake does not interrupt a stronger one.
+    ///
+    /// Panics if `decay` is not in `[0, 1)` since the shake would never end.
+    pub fn shake(&mut self, amplitude: f32, decay: f32) {
+        assert!(
+            (0. ..1.).contains(&decay),
+            "the decay of a camera shake must be in [0, 1)",
+        );
+
+        if amplitude >= self.shake {
+            self.shake = amplitude;
+            self.shake_decay = decay;
+        }
+    }
+
+    #[inline]
+    /// Whether the camera is shaking.
+    pub fn is_shaking(&self) -> bool {
+        self.shake > 0.
+    }
+
+    #[inline]
+    /// The current displacement due to shaking.
+    pub fn shake_offset(&self) -> Vector2<f32> {
+        self.shake_offset
+    }
+
+    /// Briefly zoom in (or out, for negative amounts).
+    ///
+    /// The zoom jumps by `amount` and eases back over a number of frames.
+    pub fn zoom_pulse(&mut self, amount: f32, frames: u32) {
+        self.zoom_pulse = Some(Tween::new(amount, 0., frames, Easing::QuadOut));
+    }
+
+    #[inline]
+    /// The current zoom factor.
+    pub fn zoom(&self) -> f32 {
+        1. + self.zoom_pulse.as_ref().map_or(0., Tween::value)
+    }
+
+    /// Advance the camera by one frame.
+    ///
+    /// Random numbers are only drawn while the camera is shaking.
+    pub fn step<B>(&mut self, rand: &mut Rand<B>)
+    where
+        B: RandBackend,
+    {
+        let offset = (self.target - self.position.truncate()) * self.follow_rate;
+        self.position += offset.extend(0.);
+
+        if self.is_shaking() {
+            self.shake_offset = Vector2::new(
+                rand.next_float_signed(self.shake),
+                rand.next_float_signed(self.shake),
+            );
+            self.shake *= self.shake_decay;
+            if self.shake < SHAKE_EPSILON {
+                self.shake = 0.;
+            }
+        } else {
+            self.shake_offset = Vector2::zero();
+        }
+
+        if let Some(pulse) = self.zoom_pulse.as_mut() {
+            pulse.step();
+            if pulse.is_done() {
+                self.zoom_pulse = None;
+            }
+        }
+    }
+
+    /// The view matrix for the camera.
+    ///
+    /// This should be applied after the perspective matrix, i.e., `perspective * view`.
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        let zoom = self.zoom();
+        let eye = self.position + self.shake_offset.extend(0.);
+
+        Matrix4::from_nonuniform_scale(zoom, zoom, 1.) * Matrix4::from_translation(-eye)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{Matrix4, Vector2, Vector3, Vector4, Zero};
+
+    use crate::camera::Camera;
+    use crate::rand::Rand;
+
+    #[test]
+    fn test_camera_view_matrix() {
+        let camera = Camera::new(Vector3::new(1., 2., 20.));
+
+        assert_eq!(
+            camera.view_matrix(),
+            Matrix4::from_translation(Vector3::new(-1., -2., -20.)),
+        );
+        assert_eq!(
+            camera.view_matrix() * Vector4::new(1., 2., 0., 1.),
+            Vector4::new(0., 0., -20., 1.),
+        );
+    }
+
+    #[test]
+    fn test_camera_follow() {
+        let mut rand: Rand = Rand::with_seed(1);
+        let mut camera = Camera::new(Vector3::new(0., 0., 20.));
+        camera.set_follow_rate(0.5);
+        camera.follow(Vector2::new(8., -4.));
+
+        camera.step(&mut rand);
+        assert_eq!(camera.position(), Vector3::new(4., -2., 20.));
+        camera.step(&mut rand);
+        assert_eq!(camera.position(), Vector3::new(6., -3., 20.));
+
+        camera.set_position(Vector3::new(1., 1., 10.));
+        camera.step(&mut rand);
+        assert_eq!(camera.position(), Vector3::new(1., 1., 10.));
+
+        // Without lag, the camera moves straight to the target.
+        camera.set_follow_rate(1.);
+        camera.follow(Vector2::new(3., 3.));
+        camera.step(&mut rand);
+        assert_eq!(camera.position(), Vector3::new(3., 3., 10.));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be in [0, 1)")]
+    fn test_camera_shake_endless() {
+        let mut camera = Camera::new(Vector3::new(0., 0., 20.));
+        camera.shake(1., 1.);
+    }
+
+    #[test]
+    fn test_camera_shake() {
+        let mut rand: Rand = Rand::with_seed(1);
+        let mut camera = Camera::new(Vector3::new(0., 0., 20.));
+
+        camera.shake(2., 0.5);
+        camera.shake(1., 0.9);
+        assert!(camera.is_shaking());
+
+        let mut amplitude = 2.;
+        let mut frames = 0;
+        while camera.is_shaking() {
+            camera.step(&mut rand);
+            let offset = camera.shake_offset();
+            assert!(offset.x.abs() <= amplitude && offset.y.abs() <= amplitude);
+            amplitude *= 0.5;
+            frames += 1;
+        }
+        // The stronger, faster decaying shake wins.
+        assert_eq!(frames, 11);
+
+        camera.step(&mut rand);
+        assert_eq!(camera.shake_offset(), Vector2::zero());
+        assert_eq!(
+            camera.view_matrix(),
+            Matrix4::from_translation(Vector3::new(0., 0., -20.)),
+        );
+
+        // Shaking is deterministic for a given seed.
+        let offsets = |seed| {
+            let mut rand: Rand = Rand::with_seed(seed);
+            let mut camera = Camera::new(Vector3::new(0., 0., 20.));
+            camera.shake(1., 0.9);
+            (0..10)
+                .map(|_| {
+                    camera.step(&mut rand);
+                    camera.shake_offset()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offsets(5), offsets(5));
+    }
+
+    #[test]
+    fn test_camera_zoom_pulse() {
+        let mut rand: Rand = Rand::with_seed(1);
+        let mut camera = Camera::new(Vector3::new(0., 0., 20.));
+        assert_eq!(camera.zoom(), 1.);
+
+        camera.zoom_pulse(0.5, 4);
+        assert_eq!(camera.zoom(), 1.5);
+        assert_eq!(
+            camera.view_matrix() * Vector4::new(2., 2., 0., 1.),
+            Vector4::new(3., 3., -20., 1.),
+        );
+
+        let mut last = camera.zoom();
+        (0..4).for_each(|_| {
+            camera.step(&mut rand);
+            assert!(camera.zoom() < last);
+            last = camera.zoom();
+        });
+        assert_eq!(camera.zoom(), 1.);
+    }
+}
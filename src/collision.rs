@@ -0,0 +1,482 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Collision detection
+//!
+//! Intersection tests between simple 2D shapes. Circles are given as a center and radius while
+//! axis-aligned boxes are given as their minimum and maximum corners. All tests consider touching
+//! shapes to be intersecting.
+//!
+//! The swept tests move a shape along a motion vector over a frame and report the time of impact
+//! as a fraction of the motion in `[0, 1]`. Shapes which already overlap have a time of impact of
+//! `0`. To sweep two moving shapes against each other, pass the motion of the first relative to
+//! the second.
+
+use cgmath::{InnerSpace, Vector2};
+
+#[inline]
+/// The z component of the cross product of two vectors.
+fn cross(v1: Vector2<f32>, v2: Vector2<f32>) -> f32 {
+    v1.x * v2.y - v1.y * v2.x
+}
+
+/// Determine whether two circles intersect.
+pub fn circle_circle(c1: Vector2<f32>, r1: f32, c2: Vector2<f32>, r2: f32) -> bool {
+    let radius = r1 + r2;
+    (c1 - c2).magnitude2() <= radius * radius
+}
+
+/// Determine whether a circle intersects an axis-aligned box.
+pub fn circle_aabb(
+    center: Vector2<f32>,
+    radius: f32,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+) -> bool {
+    let closest = Vector2::new(
+        center.x.max(min.x).min(max.x),
+        center.y.max(min.y).min(max.y),
+    );
+    (center - closest).magnitude2() <= radius * radius
+}
+
+/// Determine whether two axis-aligned boxes intersect.
+pub fn aabb_aabb(
+    min1: Vector2<f32>,
+    max1: Vector2<f32>,
+    min2: Vector2<f32>,
+    max2: Vector2<f32>,
+) -> bool {
+    min1.x <= max2.x && min2.x <= max1.x && min1.y <= max2.y && min2.y <= max1.y
+}
+
+/// Determine whether a point is within a polygon.
+///
+/// The polygon is given by its vertices in order and is implicitly closed. Self-intersecting
+/// polygons use the even-odd rule.
+pub fn point_in_polygon(point: Vector2<f32>, polygon: &[Vector2<f32>]) -> bool {
+    let prev = polygon.iter().cycle().skip(polygon.len().saturating_sub(1));
+
+    polygon
+        .iter()
+        .zip(prev)
+        .filter(|&(v1, v2)| {
+            (v1.y > point.y) != (v2.y > point.y)
+                && point.x < (v2.x - v1.x) * (point.y - v1.y) / (v2.y - v1.y) + v1.x
+        })
+        .count()
+        % 2
+        == 1
+}
+
+/// Determine whether a line segment intersects a circle.
+pub fn segment_circle(
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    center: Vector2<f32>,
+    radius: f32,
+) -> bool {
+    swept_circle_circle(start, 0., end - start, center, radius).is_some()
+}
+
+/// Find where two line segments intersect.
+///
+/// Returns the position of the first intersection as a fraction along the first segment.
+pub fn segment_segment(
+    a1: Vector2<f32>,
+    a2: Vector2<f32>,
+    b1: Vector2<f32>,
+    b2: Vector2<f32>,
+) -> Option<f32> {
+    let r = a2 - a1;
+    let s = b2 - b1;
+    let offset = b1 - a1;
+    let denom = cross(r, s);
+
+    if denom == 0. {
+        if cross(offset, r) != 0. || cross(offset, s) != 0. {
+            // Parallel and not collinear.
+            return None;
+        }
+
+        let rr = r.magnitude2();
+        if rr == 0. {
+            // The first segment is a point which is on the line through the second segment; see
+            // if it lies within the segment. If both segments are points, they must coincide.
+            let ss = s.magnitude2();
+            let hit = if ss == 0. {
+                offset.magnitude2() == 0.
+            } else {
+                (0. ..=1.).contains(&(-offset.dot(s) / ss))
+            };
+            return if hit { Some(0.) } else { None };
+        }
+
+        // Collinear; find the overlap of the second segment along the first.
+        let t0 = offset.dot(r) / rr;
+        let t1 = t0 + s.dot(r) / rr;
+        let low = t0.min(t1).max(0.);
+        let high = t0.max(t1).min(1.);
+
+        return if low <= high { Some(low) } else { None };
+    }
+
+    let t = cross(offset, s) / denom;
+    let u = cross(offset, r) / denom;
+
+    if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Find when a point moving along a ray hits an axis-aligned box.
+fn ray_aabb(
+    origin: Vector2<f32>,
+    motion: Vector2<f32>,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+) -> Option<f32> {
+    let slab = |origin: f32, motion: f32, min: f32, max: f32| {
+        if motion == 0. {
+            if min <= origin && origin <= max {
+                Some((0., 1.))
+            } else {
+                None
+            }
+        } else {
+            let t1 = (min - origin) / motion;
+            let t2 = (max - origin) / motion;
+            Some((t1.min(t2), t1.max(t2)))
+        }
+    };
+
+    let (x_enter, x_exit) = slab(origin.x, motion.x, min.x, max.x)?;
+    let (y_enter, y_exit) = slab(origin.y, motion.y, min.y, max.y)?;
+
+    let enter = x_enter.max(y_enter).max(0.);
+    let exit = x_exit.min(y_exit).min(1.);
+
+    if enter <= exit {
+        Some(enter)
+    } else {
+        None
+    }
+}
+
+/// Find when a moving circle hits a circle.
+pub fn swept_circle_circle(
+    center: Vector2<f32>,
+    radius: f32,
+    motion: Vector2<f32>,
+    other_center: Vector2<f32>,
+    other_radius: f32,
+) -> Option<f32> {
+    let radius = radius + other_radius;
+    let offset = center - other_center;
+    let c = offset.magnitude2() - radius * radius;
+
+    if c <= 0. {
+        return Some(0.);
+    }
+
+    let a = motion.magnitude2();
+    if a == 0. {
+        return None;
+    }
+
+    let b = offset.dot(motion);
+    let discriminant = b * b - a * c;
+    if discriminant < 0. {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / a;
+    if (0. ..=1.).contains(&t) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Find when a moving circle hits an axis-aligned box.
+pub fn swept_circle_aabb(
+    center: Vector2<f32>,
+    radius: f32,
+    motion: Vector2<f32>,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+) -> Option<f32> {
+    if circle_aabb(center, radius, min, max) {
+        return Some(0.);
+    }
+
+    // The set of positions where the circle touches the box is the box with its edges pushed out
+    // by the radius and its corners rounded off. Check the two boxes which make up its straight
+    // edges and the circles at the corners.
+    let wide = Vector2::new(radius, 0.);
+    let tall = Vector2::new(0., radius);
+    let corners = [
+        min,
+        max,
+        Vector2::new(min.x, max.y),
+        Vector2::new(max.x, min.y),
+    ];
+
+    let boxes = [
+        ray_aabb(center, motion, min - wide, max + wide),
+        ray_aabb(center, motion, min - tall, max + tall),
+    ];
+
+    boxes
+        .iter()
+        .cloned()
+        .chain(
+            corners
+                .iter()
+                .map(|&corner| swept_circle_circle(center, 0., motion, corner, radius)),
+        )
+        .flatten()
+        .fold(None, |earliest: Option<f32>, t| {
+            Some(earliest.map_or(t, |earliest| earliest.min(t)))
+        })
+}
+
+/// Find when a moving axis-aligned box hits an axis-aligned box.
+pub fn swept_aabb_aabb(
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+    motion: Vector2<f32>,
+    other_min: Vector2<f32>,
+    other_max: Vector2<f32>,
+) -> Option<f32> {
+    let half_extents = (max - min) / 2.;
+    let center = min + half_extents;
+
+    ray_aabb(
+        center,
+        motion,
+        other_min - half_extents,
+        other_max + half_extents,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::collision::*;
+
+    fn v(x: f32, y: f32) -> Vector2<f32> {
+        Vector2::new(x, y)
+    }
+
+    fn assert_toi(actual: Option<f32>, expected: Option<f32>) {
+        match (actual, expected) {
+            (Some(actual), Some(expected)) => {
+                assert!(
+                    (actual - expected).abs() < 1e-5,
+                    "{} != {}",
+                    actual,
+                    expected,
+                )
+            },
+            (actual, expected) => assert_eq!(actual, expected),
+        }
+    }
+
+    #[test]
+    fn test_circle_circle() {
+        assert!(circle_circle(v(0., 0.), 1., v(1.5, 0.), 1.));
+        assert!(circle_circle(v(0., 0.), 1., v(2., 0.), 1.));
+        assert!(!circle_circle(v(0., 0.), 1., v(2., 2.), 1.));
+    }
+
+    #[test]
+    fn test_circle_aabb() {
+        let (min, max) = (v(-1., -1.), v(1., 1.));
+
+        assert!(circle_aabb(v(0., 0.), 0.1, min, max));
+        assert!(circle_aabb(v(2., 0.), 1., min, max));
+        assert!(!circle_aabb(v(2., 2.), 1., min, max));
+        assert!(circle_aabb(v(1.5, 1.5), 0.75, min, max));
+    }
+
+    #[test]
+    fn test_aabb_aabb() {
+        let (min, max) = (v(-1., -1.), v(1., 1.));
+
+        assert!(aabb_aabb(min, max, v(0., 0.), v(3., 3.)));
+        assert!(aabb_aabb(min, max, v(1., 1.), v(3., 3.)));
+        assert!(aabb_aabb(min, max, v(-2., -2.), v(2., 2.)));
+        assert!(!aabb_aabb(min, max, v(1.5, -1.), v(3., 1.)));
+        assert!(!aabb_aabb(min, max, v(-1., 1.5), v(1., 3.)));
+    }
+
+    #[test]
+    fn test_point_in_polygon() {
+        let square = [v(0., 0.), v(2., 0.), v(2., 2.), v(0., 2.)];
+        let concave = [v(0., 0.), v(4., 0.), v(4., 4.), v(2., 1.), v(0., 4.)];
+
+        assert!(point_in_polygon(v(1., 1.), &square));
+        assert!(!point_in_polygon(v(3., 1.), &square));
+        assert!(!point_in_polygon(v(-1., 1.), &square));
+        assert!(point_in_polygon(v(1., 0.5), &concave));
+        assert!(point_in_polygon(v(3.5, 3.), &concave));
+        assert!(!point_in_polygon(v(2., 3.), &concave));
+        assert!(!point_in_polygon(v(0., 0.), &[]));
+    }
+
+    #[test]
+    fn test_segment_circle() {
+        assert!(segment_circle(v(-2., 0.), v(2., 0.), v(0., 0.), 0.5));
+        assert!(segment_circle(v(-2., 0.5), v(2., 0.5), v(0., 0.), 0.5));
+        assert!(!segment_circle(v(-2., 1.), v(2., 1.), v(0., 0.), 0.5));
+        assert!(!segment_circle(v(-2., 0.), v(-1., 0.), v(0., 0.), 0.5));
+        assert!(segment_circle(v(-0.1, 0.), v(0.1, 0.), v(0., 0.), 0.5));
+    }
+
+    #[test]
+    fn test_segment_segment() {
+        assert_toi(
+            segment_segment(v(0., 0.), v(2., 2.), v(0., 2.), v(2., 0.)),
+            Some(0.5),
+        );
+        assert_toi(
+            segment_segment(v(0., 0.), v(1., 0.), v(0., 1.), v(1., 1.)),
+            None,
+        );
+        assert_toi(
+            segment_segment(v(0., 0.), v(1., 1.), v(2., 0.), v(0., 2.)),
+            Some(1.),
+        );
+        assert_toi(
+            segment_segment(v(0., 0.), v(1., 1.), v(3., 0.), v(0., 3.)),
+            None,
+        );
+        // Collinear segments.
+        assert_toi(
+            segment_segment(v(0., 0.), v(4., 0.), v(1., 0.), v(6., 0.)),
+            Some(0.25),
+        );
+        assert_toi(
+            segment_segment(v(0., 0.), v(4., 0.), v(6., 0.), v(5., 0.)),
+            None,
+        );
+        // Degenerate segments.
+        assert_toi(
+            segment_segment(v(1., 0.), v(1., 0.), v(0., 0.), v(2., 0.)),
+            Some(0.),
+        );
+        assert_toi(
+            segment_segment(v(1., 1.), v(1., 1.), v(0., 0.), v(2., 0.)),
+            None,
+        );
+        assert_toi(
+            segment_segment(v(0.3, 0.3), v(0.3, 0.3), v(0.1, 0.1), v(0.7, 0.7)),
+            Some(0.),
+        );
+        assert_toi(
+            segment_segment(v(0.9, 0.9), v(0.9, 0.9), v(0.1, 0.1), v(0.7, 0.7)),
+            None,
+        );
+        assert_toi(
+            segment_segment(v(1., 1.), v(1., 1.), v(1., 1.), v(1., 1.)),
+            Some(0.),
+        );
+        assert_toi(
+            segment_segment(v(1., 1.), v(1., 1.), v(1., 2.), v(1., 2.)),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_swept_circle_circle() {
+        assert_toi(
+            swept_circle_circle(v(-4., 0.), 1., v(8., 0.), v(0., 0.), 1.),
+            Some(0.25),
+        );
+        assert_toi(
+            swept_circle_circle(v(0.5, 0.), 1., v(8., 0.), v(0., 0.), 1.),
+            Some(0.),
+        );
+        assert_toi(
+            swept_circle_circle(v(-4., 3.), 1., v(8., 0.), v(0., 0.), 1.),
+            None,
+        );
+        assert_toi(
+            swept_circle_circle(v(-4., 0.), 1., v(1., 0.), v(0., 0.), 1.),
+            None,
+        );
+        assert_toi(
+            swept_circle_circle(v(4., 0.), 1., v(8., 0.), v(0., 0.), 1.),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_swept_does_not_tunnel() {
+        // A fast, small bullet passing completely through a thin enemy within a single frame.
+        let (start, motion) = (v(-10., 0.), v(20., 0.));
+        let (min, max) = (v(-0.1, -2.), v(0.1, 2.));
+
+        assert!(!circle_aabb(start, 0.1, min, max));
+        assert!(!circle_aabb(start + motion, 0.1, min, max));
+        assert_toi(
+            swept_circle_aabb(start, 0.1, motion, min, max),
+            Some(9.8 / 20.),
+        );
+        assert_toi(
+            swept_aabb_aabb(v(-10.1, -0.1), v(-9.9, 0.1), motion, min, max),
+            Some(9.8 / 20.),
+        );
+    }
+
+    #[test]
+    fn test_swept_circle_aabb() {
+        let (min, max) = (v(-1., -1.), v(1., 1.));
+
+        assert_toi(
+            swept_circle_aabb(v(-4., 0.), 1., v(4., 0.), min, max),
+            Some(0.5),
+        );
+        assert_toi(
+            swept_circle_aabb(v(0., 0.), 1., v(4., 0.), min, max),
+            Some(0.),
+        );
+        assert_toi(swept_circle_aabb(v(-4., 3.), 1., v(8., 0.), min, max), None);
+        // Stopping just short of a corner does not hit it even though a box expanded by the radius
+        // would.
+        assert_toi(
+            swept_circle_aabb(v(2.5, 2.5), 1., v(-0.7, -0.7), min, max),
+            None,
+        );
+        // Moving diagonally toward the corner hits it at a distance of the radius.
+        let toi = swept_circle_aabb(v(3., 3.), 1., v(-2., -2.), min, max).unwrap();
+        let center = v(3., 3.) + v(-2., -2.) * toi;
+        assert!(((center - max).magnitude() - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_swept_aabb_aabb() {
+        let (min, max) = (v(-1., -1.), v(1., 1.));
+
+        assert_toi(
+            swept_aabb_aabb(v(-5., -1.), v(-3., 1.), v(4., 0.), min, max),
+            Some(0.5),
+        );
+        assert_toi(
+            swept_aabb_aabb(v(-1., -1.), v(1., 1.), v(4., 0.), min, max),
+            Some(0.),
+        );
+        assert_toi(
+            swept_aabb_aabb(v(-5., 2.), v(-3., 4.), v(8., 0.), min, max),
+            None,
+        );
+        assert_toi(
+            swept_aabb_aabb(v(-5., -5.), v(-3., -3.), v(4., 4.), min, max),
+            Some(0.5),
+        );
+    }
+}
@@ -0,0 +1,472 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Colors
+//!
+//! Colors are stored as linear RGBA components, which is what shaders rendering to a
+//! `TargetFormat` should output; the conversion to sRGB happens when the target is written. Colors
+//! written in sRGB, such as those picked in an image editor, should be converted with
+//! `Color::from_srgb` first.
+
+use std::convert::TryFrom;
+
+use crate::tween::Tweenable;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// A color with an alpha channel.
+pub struct Color {
+    /// The red component.
+    pub r: f32,
+    /// The green component.
+    pub g: f32,
+    /// The blue component.
+    pub b: f32,
+    /// The alpha component.
+    pub a: f32,
+}
+
+#[inline]
+/// Convert an sRGB component to linear.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[inline]
+/// Convert a linear component to sRGB.
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Compute the RGB components for a hue in degrees given the chroma and an offset.
+fn hue_to_rgb(hue: f32, chroma: f32, offset: f32) -> (f32, f32, f32) {
+    let sector = hue.rem_euclid(360.) / 60.;
+    let x = chroma * (1. - (sector % 2. - 1.).abs());
+
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    (r + offset, g + offset, b + offset)
+}
+
+impl Color {
+    /// Opaque white.
+    pub const WHITE: Self = Color {
+        r: 1.,
+        g: 1.,
+        b: 1.,
+        a: 1.,
+    };
+    /// Opaque black.
+    pub const BLACK: Self = Color {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 1.,
+    };
+    /// Fully transparent black.
+    pub const TRANSPARENT: Self = Color {
+        r: 0.,
+        g: 0.,
+        b: 0.,
+        a: 0.,
+    };
+
+    #[inline]
+    /// Create a new color.
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Color {
+            r,
+            g,
+            b,
+            a,
+        }
+    }
+
+    #[inline]
+    /// Create a new opaque color.
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.)
+    }
+
+    /// Create a color from hue (in degrees), saturation, and value.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        let chroma = value * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma, value - chroma);
+        Self::new(r, g, b, alpha)
+    }
+
+    /// Create a color from hue (in degrees), saturation, and lightness.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma, lightness - chroma / 2.);
+        Self::new(r, g, b, alpha)
+    }
+
+    /// The largest and smallest RGB components and the hue in degrees.
+    fn hue_range(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+
+        let hue = if delta == 0. {
+            0.
+        } else if max == self.r {
+            60. * ((self.g - self.b) / delta).rem_euclid(6.)
+        } else if max == self.g {
+            60. * ((self.b - self.r) / delta + 2.)
+        } else {
+            60. * ((self.r - self.g) / delta + 4.)
+        };
+
+        (max, min, hue)
+    }
+
+    /// The hue (in degrees), saturation, and value of the color.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (max, min, hue) = self.hue_range();
+        let saturation = if max == 0. { 0. } else { (max - min) / max };
+
+        (hue, saturation, max)
+    }
+
+    /// The hue (in degrees), saturation, and lightness of the color.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (max, min, hue) = self.hue_range();
+        let lightness = (max + min) / 2.;
+        let saturation = if max == min {
+            0.
+        } else {
+            (max - min) / (1. - (2. * lightness - 1.).abs())
+        };
+
+        (hue, saturation, lightness)
+    }
+
+    /// Convert a color with sRGB components to linear components.
+    ///
+    /// Alpha is unchanged.
+    pub fn from_srgb(srgb: Self) -> Self {
+        Self::new(
+            srgb_to_linear(srgb.r),
+            srgb_to_linear(srgb.g),
+            srgb_to_linear(srgb.b),
+            srgb.a,
+        )
+    }
+
+    /// Convert the color to sRGB components.
+    ///
+    /// Alpha is unchanged.
+    pub fn to_srgb(&self) -> Self {
+        Self::new(
+            linear_to_srgb(self.r),
+            linear_to_srgb(self.g),
+            linear_to_srgb(self.b),
+            self.a,
+        )
+    }
+
+    #[inline]
+    /// Interpolate between two colors.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    #[inline]
+    /// Scale the brightness of the color.
+    ///
+    /// Alpha is unchanged. This is suitable for implementing a brightness option.
+    pub fn scale_brightness(&self, brightness: f32) -> Self {
+        Self::new(
+            self.r * brightness,
+            self.g * brightness,
+            self.b * brightness,
+            self.a,
+        )
+    }
+
+    #[inline]
+    /// Replace the alpha of the color.
+    pub fn with_alpha(&self, alpha: f32) -> Self {
+        Self::new(self.r, self.g, self.b, alpha)
+    }
+
+    #[inline]
+    /// The components of the color as an array for passing to shaders.
+    pub fn to_array(&self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(c: [f32; 4]) -> Self {
+        Self::new(c[0], c[1], c[2], c[3])
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(c: Color) -> Self {
+        c.to_array()
+    }
+}
+
+impl Tweenable for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::lerp(self, other, t)
+    }
+}
+
+/// Cycle smoothly through a palette of colors.
+///
+/// The animator is advanced once per frame. Each color is blended into the next over a fixed
+/// number of frames and the palette repeats.
+#[derive(Debug, Clone)]
+pub struct PaletteAnimator {
+    /// The colors in the palette.
+    colors: Vec<Color>,
+    /// The number of frames to spend on each color.
+    frames_per_color: u32,
+    /// The number of frames in a full cycle of the palette.
+    period: u32,
+    /// The current frame.
+    frame: u32,
+}
+
+impl PaletteAnimator {
+    /// Create a new animator.
+    ///
+    /// Panics if the palette is empty, a color has no frames or a full cycle of the palette has
+    /// more than `u32::MAX` frames.
+    pub fn new(colors: Vec<Color>, frames_per_color: u32) -> Self {
+        assert!(!colors.is_empty(), "a palette requires at least one color");
+        assert!(
+            frames_per_color > 0,
+            "a palette color requires at least one frame"
+        );
+        let period = u32::try_from(colors.len())
+            .ok()
+            .and_then(|len| len.checked_mul(frames_per_color))
+            .expect("the palette has too many frames");
+
+        PaletteAnimator {
+            colors,
+            frames_per_color,
+            period,
+            frame: 0,
+        }
+    }
+
+    #[inline]
+    /// The number of frames in a full cycle of the palette.
+    pub fn period(&self) -> u32 {
+        self.period
+    }
+
+    #[inline]
+    /// Advance the animation by one frame.
+    pub fn step(&mut self) {
+        // The frame is below the period, so this cannot overflow.
+        self.frame = (self.frame + 1) % self.period;
+    }
+
+    #[inline]
+    /// Restart the animation.
+    pub fn reset(&mut self) {
+        self.frame = 0;
+    }
+
+    #[inline]
+    /// The current color.
+    pub fn color(&self) -> Color {
+        self.color_at(0)
+    }
+
+    /// The color a number of frames ahead of the current one.
+    ///
+    /// Offsetting objects by different amounts makes the colors ripple across them.
+    pub fn color_at(&self, offset: u32) -> Color {
+        let frame = ((u64::from(self.frame) + u64::from(offset)) % u64::from(self.period)) as u32;
+        let index = (frame / self.frames_per_color) as usize;
+        let t = (frame % self.frames_per_color) as f32 / self.frames_per_color as f32;
+
+        let from = &self.colors[index];
+        let to = &self.colors[(index + 1) % self.colors.len()];
+        from.lerp(to, t)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::color::{Color, PaletteAnimator};
+    use crate::tween::{Easing, Tween};
+
+    fn assert_close(actual: Color, expected: Color) {
+        assert!(
+            actual
+                .to_array()
+                .iter()
+                .zip(expected.to_array().iter())
+                .all(|(a, e)| (a - e).abs() < 1e-5),
+            "{:?} != {:?}",
+            actual,
+            expected,
+        );
+    }
+
+    fn assert_close3(actual: (f32, f32, f32), expected: (f32, f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4
+                && (actual.1 - expected.1).abs() < 1e-5
+                && (actual.2 - expected.2).abs() < 1e-5,
+            "{:?} != {:?}",
+            actual,
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_color_array() {
+        let color = Color::new(0.1, 0.2, 0.3, 0.4);
+        let array: [f32; 4] = color.into();
+        assert_eq!(array, [0.1, 0.2, 0.3, 0.4]);
+        assert_eq!(Color::from(array), color);
+        assert_eq!(Color::rgb(1., 1., 1.), Color::WHITE);
+        assert_eq!(Color::BLACK.with_alpha(0.), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn test_color_hsv() {
+        assert_close(Color::from_hsv(0., 1., 1., 1.), Color::rgb(1., 0., 0.));
+        assert_close(Color::from_hsv(120., 1., 1., 1.), Color::rgb(0., 1., 0.));
+        assert_close(
+            Color::from_hsv(240., 1., 0.5, 0.5),
+            Color::new(0., 0., 0.5, 0.5),
+        );
+        assert_close(Color::from_hsv(60., 0.5, 1., 1.), Color::rgb(1., 1., 0.5));
+        assert_close(Color::from_hsv(-60., 1., 1., 1.), Color::rgb(1., 0., 1.));
+        assert_close(Color::from_hsv(420., 1., 1., 1.), Color::rgb(1., 1., 0.));
+        assert_close(
+            Color::from_hsv(90., 0., 0.25, 1.),
+            Color::rgb(0.25, 0.25, 0.25),
+        );
+
+        (0..360).step_by(15).for_each(|hue| {
+            let hsv = (hue as f32, 0.75, 0.5);
+            let color = Color::from_hsv(hsv.0, hsv.1, hsv.2, 1.);
+            assert_close3(color.to_hsv(), hsv);
+        });
+        assert_eq!(Color::BLACK.to_hsv(), (0., 0., 0.));
+    }
+
+    #[test]
+    fn test_color_hsl() {
+        assert_close(Color::from_hsl(0., 1., 0.5, 1.), Color::rgb(1., 0., 0.));
+        assert_close(
+            Color::from_hsl(180., 1., 0.25, 1.),
+            Color::rgb(0., 0.5, 0.5),
+        );
+        assert_close(
+            Color::from_hsl(0., 0., 0.75, 1.),
+            Color::rgb(0.75, 0.75, 0.75),
+        );
+        assert_close(Color::from_hsl(300., 1., 1., 1.), Color::WHITE);
+
+        (0..360).step_by(15).for_each(|hue| {
+            let hsl = (hue as f32, 0.5, 0.4);
+            let color = Color::from_hsl(hsl.0, hsl.1, hsl.2, 1.);
+            assert_close3(color.to_hsl(), hsl);
+        });
+        assert_eq!(Color::WHITE.to_hsl(), (0., 0., 1.));
+    }
+
+    #[test]
+    fn test_color_srgb() {
+        assert_close(Color::from_srgb(Color::WHITE), Color::WHITE);
+        assert_close(Color::from_srgb(Color::BLACK), Color::BLACK);
+
+        let mid = Color::from_srgb(Color::new(0.5, 0.5, 0.5, 0.5));
+        assert!((mid.r - 0.214_041).abs() < 1e-5);
+        assert_eq!(mid.a, 0.5);
+
+        (0..=20).for_each(|i| {
+            let c = i as f32 / 20.;
+            let color = Color::new(c, c / 2., c / 4., c);
+            assert_close(Color::from_srgb(color).to_srgb(), color);
+        });
+    }
+
+    #[test]
+    fn test_color_lerp_and_brightness() {
+        let from = Color::new(0., 0.5, 1., 1.);
+        let to = Color::new(1., 0.5, 0., 0.);
+        assert_close(from.lerp(&to, 0.25), Color::new(0.25, 0.5, 0.75, 0.75));
+
+        assert_close(from.scale_brightness(0.5), Color::new(0., 0.25, 0.5, 1.));
+
+        let mut tween = Tween::new(from, to, 4, Easing::Linear);
+        tween.step();
+        tween.step();
+        assert_close(tween.value(), Color::new(0.5, 0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn test_palette_animator() {
+        let red = Color::rgb(1., 0., 0.);
+        let blue = Color::rgb(0., 0., 1.);
+        let mut palette = PaletteAnimator::new(vec![red, blue], 4);
+
+        assert_eq!(palette.period(), 8);
+        assert_eq!(palette.color(), red);
+        palette.step();
+        assert_close(palette.color(), Color::rgb(0.75, 0., 0.25));
+        assert_close(palette.color_at(3), blue);
+        // Colors wrap around to the start of the palette.
+        assert_close(palette.color_at(7), red);
+        assert_close(palette.color_at(5), Color::rgb(0.5, 0., 0.5));
+        assert_close(palette.color_at(13), Color::rgb(0.5, 0., 0.5));
+
+        (0..7).for_each(|_| palette.step());
+        assert_eq!(palette.color(), red);
+        palette.step();
+        palette.reset();
+        assert_eq!(palette.color(), red);
+    }
+
+    #[test]
+    fn test_palette_animator_large_offsets() {
+        let red = Color::rgb(1., 0., 0.);
+        let blue = Color::rgb(0., 0., 1.);
+        let mut palette = PaletteAnimator::new(vec![red, blue], u32::MAX / 2);
+
+        assert_eq!(palette.period(), u32::MAX - 1);
+        palette.step();
+        assert_eq!(palette.color_at(u32::MAX - 2), red);
+        assert_close(palette.color_at(u32::MAX / 2 - 1), blue);
+        assert_close(palette.color_at(u32::MAX), palette.color_at(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "the palette has too many frames")]
+    fn test_palette_animator_too_many_frames() {
+        PaletteAnimator::new(vec![Color::WHITE, Color::BLACK, Color::WHITE], u32::MAX / 2);
+    }
+}
@@ -0,0 +1,686 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Importing save data from the original games
+//!
+//! The original games store their high scores in `*.prf` files next to the executable: a version
+//! number followed by a fixed sequence of little-endian 32-bit integers. The files also remember
+//! the mode, difficulty, or level last selected on the title screen. They have no configuration
+//! file; options are passed on the command line, usually from a shortcut or a script, so the
+//! remaining settings are imported from those arguments instead.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use cgmath::Vector2;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::paths::Paths;
+use crate::scores::{ScoreEntry, ScoreTable, ScoresError};
+use crate::settings::{GameSettings, Settings, SettingsError};
+
+/// Errors which may occur when importing save data.
+#[derive(Debug, Error)]
+pub enum ImportError {
+    /// An error reading the file.
+    #[error("failed to read save data: {}", _0)]
+    Io(#[from] io::Error),
+    /// The file ended in the middle of a record.
+    #[error("save data is truncated at byte {}", offset)]
+    Truncated {
+        /// The offset of the incomplete value.
+        offset: usize,
+    },
+    /// The file has extra data after the records.
+    #[error("save data has {} unexpected trailing bytes", length)]
+    TrailingData {
+        /// The number of extra bytes.
+        length: usize,
+    },
+    /// The file was written by an unsupported version of the game.
+    #[error("unsupported {} save data version {}", game, version)]
+    UnsupportedVersion {
+        /// The name of the game.
+        game: &'static str,
+        /// The version in the file.
+        version: i32,
+    },
+    /// A value which must not be negative is.
+    #[error("invalid negative value {} at byte {}", value, offset)]
+    NegativeValue {
+        /// The offset of the value.
+        offset: usize,
+        /// The value.
+        value: i32,
+    },
+    /// A command line option has a missing or invalid value.
+    #[error("invalid value for option {}", _0)]
+    InvalidOption(String),
+    /// An error reading or writing the imported score tables.
+    #[error("failed to import scores: {}", _0)]
+    Scores(#[from] ScoresError),
+    /// An error loading the settings to import into.
+    #[error("failed to import settings: {}", _0)]
+    Settings(#[from] SettingsError),
+    /// An error saving the imported settings.
+    #[error("failed to save imported settings: {}", _0)]
+    SaveSettings(#[source] io::Error),
+}
+
+/// A convenience alias for import results.
+pub type ImportResult<T> = Result<T, ImportError>;
+
+/// A reader for the little-endian integer records of `*.prf` files.
+struct PrfReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> PrfReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        PrfReader {
+            data,
+            offset: 0,
+        }
+    }
+
+    fn read_i32(&mut self) -> ImportResult<i32> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + 4)
+            .ok_or(ImportError::Truncated {
+                offset: self.offset,
+            })?;
+        self.offset += 4;
+
+        Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_u32(&mut self) -> ImportResult<u32> {
+        let offset = self.offset;
+        let value = self.read_i32()?;
+
+        if value < 0 {
+            Err(ImportError::NegativeValue {
+                offset,
+                value,
+            })
+        } else {
+            Ok(value as u32)
+        }
+    }
+
+    fn finish(self) -> ImportResult<()> {
+        let length = self.data.len() - self.offset;
+
+        if length == 0 {
+            Ok(())
+        } else {
+            Err(ImportError::TrailingData {
+                length,
+            })
+        }
+    }
+}
+
+/// An original game whose save data may be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyGame {
+    /// Gunroar.
+    ///
+    /// `gr.prf` is version `14`: the high score of each of the four game modes followed by the
+    /// last selected mode. Version `13` predates the mouse mode and has three high scores.
+    Gunroar,
+    /// Torus Trooper.
+    ///
+    /// `tt.prf` is version `20`: for each of the three grades, the level reached, the high score
+    /// and the start and end levels of the best run, followed by the last selected grade and
+    /// level.
+    TorusTrooper,
+}
+
+/// The Gunroar game modes, in file order.
+const GUNROAR_MODES: [&str; 4] = ["normal", "twin-stick", "double-play", "mouse"];
+/// The Torus Trooper grades, in file order.
+const TORUS_TROOPER_GRADES: [&str; 3] = ["normal", "hard", "extreme"];
+
+impl LegacyGame {
+    /// The name of the game.
+    pub fn name(self) -> &'static str {
+        match self {
+            LegacyGame::Gunroar => "Gunroar",
+            LegacyGame::TorusTrooper => "Torus Trooper",
+        }
+    }
+
+    /// The name of the file the game stores its high scores in.
+    pub fn pref_file(self) -> &'static str {
+        match self {
+            LegacyGame::Gunroar => "gr.prf",
+            LegacyGame::TorusTrooper => "tt.prf",
+        }
+    }
+}
+
+/// A high score from an original game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyScore {
+    /// The game mode of the score.
+    pub mode: &'static str,
+    /// The difficulty of the score.
+    pub difficulty: &'static str,
+    /// The score.
+    pub score: u64,
+    /// The stage reached, if the game records it.
+    pub stage: u32,
+}
+
+impl LegacyScore {
+    /// Convert into a score table entry.
+    ///
+    /// The original games record neither a name nor a date for their high scores.
+    pub fn to_entry<N>(&self, name: N, date: DateTime<Utc>) -> ScoreEntry
+    where
+        N: Into<String>,
+    {
+        ScoreEntry::with_date(name, self.score, self.stage, date)
+    }
+}
+
+/// The high scores stored by an original game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyPrefs {
+    /// The game the scores are from.
+    pub game: LegacyGame,
+    /// The version of the file.
+    pub version: i32,
+    /// The high scores; modes which have never been played are omitted.
+    pub scores: Vec<LegacyScore>,
+    /// The game mode last selected, if the game records it.
+    pub last_mode: Option<&'static str>,
+    /// The difficulty last selected, if the game records it.
+    pub last_difficulty: Option<&'static str>,
+    /// The starting stage last selected, if the game records it.
+    pub last_stage: Option<u32>,
+}
+
+/// The name at an index read from a `*.prf` file, if it is in range.
+fn legacy_name(names: &[&'static str], index: i32) -> Option<&'static str> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| names.get(index).copied())
+}
+
+impl LegacyPrefs {
+    /// Parse the contents of a `*.prf` file.
+    pub fn parse(game: LegacyGame, data: &[u8]) -> ImportResult<Self> {
+        let mut reader = PrfReader::new(data);
+        let version = reader.read_i32()?;
+        let unsupported = ImportError::UnsupportedVersion {
+            game: game.name(),
+            version,
+        };
+
+        let mut last_mode = None;
+        let mut last_difficulty = None;
+        let mut last_stage = None;
+
+        let scores = match game {
+            LegacyGame::Gunroar => {
+                let modes = match version {
+                    13 => &GUNROAR_MODES[..3],
+                    14 => &GUNROAR_MODES[..],
+                    _ => return Err(unsupported),
+                };
+                let scores = modes
+                    .iter()
+                    .map(|&mode| {
+                        Ok(LegacyScore {
+                            mode,
+                            difficulty: "normal",
+                            score: reader.read_u32()?.into(),
+                            stage: 0,
+                        })
+                    })
+                    .collect::<ImportResult<Vec<_>>>()?;
+                last_mode = legacy_name(modes, reader.read_i32()?);
+                scores
+            },
+            LegacyGame::TorusTrooper => {
+                if version != 20 {
+                    return Err(unsupported);
+                }
+                let scores = TORUS_TROOPER_GRADES
+                    .iter()
+                    .map(|&difficulty| {
+                        let stage = reader.read_u32()?;
+                        let score = reader.read_u32()?.into();
+                        // The start and end levels of the best run.
+                        reader.read_i32()?;
+                        reader.read_i32()?;
+
+                        Ok(LegacyScore {
+                            mode: "normal",
+                            difficulty,
+                            score,
+                            stage,
+                        })
+                    })
+                    .collect::<ImportResult<Vec<_>>>()?;
+                last_difficulty = legacy_name(&TORUS_TROOPER_GRADES, reader.read_i32()?);
+                last_stage = Some(reader.read_u32()?);
+                scores
+            },
+        };
+        reader.finish()?;
+
+        Ok(LegacyPrefs {
+            game,
+            version,
+            scores: scores.into_iter().filter(|score| score.score > 0).collect(),
+            last_mode,
+            last_difficulty,
+            last_stage,
+        })
+    }
+
+    /// Load a `*.prf` file.
+    pub fn load<P>(game: LegacyGame, path: P) -> ImportResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(game, &fs::read(path)?)
+    }
+
+    /// Add the high scores to the score tables.
+    ///
+    /// Each score is inserted as an entry by `name` at `date` into the table for its mode and
+    /// difficulty, keeping any scores already there. Scores which are already in their table with
+    /// the same name and stage are skipped so that importing again changes nothing, whatever the
+    /// date. Returns the path of each table and the rank the imported score achieved in it, if it
+    /// was added.
+    pub fn import_scores(
+        &self,
+        paths: &Paths,
+        name: &str,
+        date: DateTime<Utc>,
+        capacity: usize,
+    ) -> ImportResult<Vec<(PathBuf, Option<usize>)>> {
+        self.scores
+            .iter()
+            .map(|score| {
+                let path = ScoreTable::path(paths, score.mode, score.difficulty)?;
+                let mut table = ScoreTable::load(&path, capacity)?;
+                let entry = score.to_entry(name, date);
+                let is_imported = table.entries().iter().any(|existing| {
+                    existing.name == entry.name
+                        && existing.score == entry.score
+                        && existing.stage == entry.stage
+                });
+                if is_imported {
+                    return Ok((path, None));
+                }
+
+                let rank = table.insert(entry);
+                if rank.is_some() {
+                    table.save(&path)?;
+                }
+
+                Ok((path, rank))
+            })
+            .collect()
+    }
+
+    /// Apply the selections remembered by the game to settings.
+    ///
+    /// Selections which the game does not record are left alone.
+    pub fn import_selections(&self, settings: &mut GameSettings) {
+        if let Some(mode) = self.last_mode {
+            settings.last_mode = Some(mode.into());
+        }
+        if let Some(difficulty) = self.last_difficulty {
+            settings.last_difficulty = Some(difficulty.into());
+        }
+        if let Some(stage) = self.last_stage {
+            settings.last_stage = Some(stage);
+        }
+    }
+}
+
+/// Apply the command line options of an original game to settings.
+///
+/// The options shared by the original games are understood:
+///
+/// * `-brightness N`: the brightness, from `0` to `100`; larger values are clamped;
+/// * `-res W H`: the window size;
+/// * `-window` and `-fullscreen`; and
+/// * `-nosound`: disables music and sound effects.
+///
+/// Returns the arguments which were not understood, such as game-specific options.
+pub fn import_legacy_options<I, S>(
+    settings: &mut GameSettings,
+    args: I,
+) -> ImportResult<Vec<String>>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let mut ignored = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next()
+                .and_then(|value| value.parse::<u32>().ok())
+                .ok_or_else(|| ImportError::InvalidOption(option.into()))
+        };
+
+        match arg.as_str() {
+            "-brightness" => {
+                let brightness = value(&arg)?.min(100);
+                settings.brightness = brightness as f32 / 100.;
+            },
+            "-res" => {
+                let width = value(&arg)?;
+                let height = value(&arg)?;
+                settings.window_size = Vector2::new(width, height);
+            },
+            "-window" => settings.windowed = true,
+            "-fullscreen" => settings.windowed = false,
+            "-nosound" => {
+                settings.music_enabled = false;
+                settings.sfx_enabled = false;
+            },
+            _ => ignored.push(arg),
+        }
+    }
+
+    Ok(ignored)
+}
+
+/// Import the settings of an original game into the saved settings.
+///
+/// The settings in the configuration directory are loaded and the selections from `prefs`, if
+/// given, and the command line options in `args` are applied to them before they are saved again.
+/// Returns the saved settings and the arguments which were not understood.
+pub fn import_legacy_settings<I, S>(
+    paths: &Paths,
+    prefs: Option<&LegacyPrefs>,
+    args: I,
+) -> ImportResult<(GameSettings, Vec<String>)>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    // Corrupt settings are replaced by the imported ones.
+    let (mut settings, _) = GameSettings::load(paths)?;
+
+    if let Some(prefs) = prefs {
+        prefs.import_selections(&mut settings);
+    }
+    let ignored = import_legacy_options(&mut settings, args)?;
+
+    settings.save(paths).map_err(ImportError::SaveSettings)?;
+
+    Ok((settings, ignored))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use cgmath::Vector2;
+    use chrono::{TimeZone, Utc};
+
+    use crate::import::{
+        import_legacy_options, import_legacy_settings, ImportError, LegacyGame, LegacyPrefs,
+        LegacyScore,
+    };
+    use crate::paths::Paths;
+    use crate::scores::{ScoreEntry, ScoreTable};
+    use crate::settings::{GameSettings, Settings};
+    use crate::test_util::TempDir;
+
+    const GUNROAR: &[u8] = include_bytes!("../tests/fixtures/legacy/gr.prf");
+    const GUNROAR_V13: &[u8] = include_bytes!("../tests/fixtures/legacy/gr-v13.prf");
+    const TORUS_TROOPER: &[u8] = include_bytes!("../tests/fixtures/legacy/tt.prf");
+
+    fn score(mode: &'static str, difficulty: &'static str, score: u64, stage: u32) -> LegacyScore {
+        LegacyScore {
+            mode,
+            difficulty,
+            score,
+            stage,
+        }
+    }
+
+    #[test]
+    fn test_import_gunroar() {
+        let prefs = LegacyPrefs::parse(LegacyGame::Gunroar, GUNROAR).unwrap();
+        assert_eq!(prefs.version, 14);
+        assert_eq!(
+            prefs.scores,
+            vec![
+                score("normal", "normal", 1_234_560, 0),
+                score("twin-stick", "normal", 98760, 0),
+                score("mouse", "normal", 4321, 0),
+            ],
+        );
+        assert_eq!(prefs.last_mode, Some("twin-stick"));
+        assert_eq!(prefs.last_difficulty, None);
+        assert_eq!(prefs.last_stage, None);
+
+        let prefs = LegacyPrefs::parse(LegacyGame::Gunroar, GUNROAR_V13).unwrap();
+        assert_eq!(prefs.version, 13);
+        assert_eq!(
+            prefs.scores,
+            vec![
+                score("normal", "normal", 55500, 0),
+                score("double-play", "normal", 700, 0),
+            ],
+        );
+        assert_eq!(prefs.last_mode, Some("double-play"));
+
+        // Unknown modes are not selected.
+        let mut unknown = GUNROAR_V13.to_vec();
+        unknown[16..20].copy_from_slice(&3i32.to_le_bytes());
+        let prefs = LegacyPrefs::parse(LegacyGame::Gunroar, &unknown).unwrap();
+        assert_eq!(prefs.last_mode, None);
+    }
+
+    #[test]
+    fn test_import_torus_trooper() {
+        let prefs = LegacyPrefs::parse(LegacyGame::TorusTrooper, TORUS_TROOPER).unwrap();
+        assert_eq!(prefs.version, 20);
+        assert_eq!(
+            prefs.scores,
+            vec![
+                score("normal", "normal", 345_670, 21),
+                score("normal", "hard", 12340, 11),
+            ],
+        );
+        assert_eq!(prefs.last_mode, None);
+        assert_eq!(prefs.last_difficulty, Some("hard"));
+        assert_eq!(prefs.last_stage, Some(6));
+
+        let mut settings = GameSettings {
+            last_mode: Some("normal".into()),
+            ..GameSettings::default()
+        };
+        prefs.import_selections(&mut settings);
+        assert_eq!(settings.last_mode.as_deref(), Some("normal"));
+        assert_eq!(settings.last_difficulty.as_deref(), Some("hard"));
+        assert_eq!(settings.last_stage, Some(6));
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        assert!(matches!(
+            LegacyPrefs::parse(LegacyGame::TorusTrooper, GUNROAR),
+            Err(ImportError::UnsupportedVersion {
+                version: 14,
+                ..
+            }),
+        ));
+        assert!(matches!(
+            LegacyPrefs::parse(LegacyGame::Gunroar, &GUNROAR[..GUNROAR.len() - 2]),
+            Err(ImportError::Truncated {
+                offset: 20
+            }),
+        ));
+        assert!(matches!(
+            LegacyPrefs::parse(LegacyGame::Gunroar, &[]),
+            Err(ImportError::Truncated {
+                offset: 0
+            }),
+        ));
+
+        let mut extra = GUNROAR_V13.to_vec();
+        extra.push(0);
+        assert!(matches!(
+            LegacyPrefs::parse(LegacyGame::Gunroar, &extra),
+            Err(ImportError::TrailingData {
+                length: 1
+            }),
+        ));
+
+        let mut negative = GUNROAR.to_vec();
+        negative[4..8].copy_from_slice(&(-1i32).to_le_bytes());
+        assert!(matches!(
+            LegacyPrefs::parse(LegacyGame::Gunroar, &negative),
+            Err(ImportError::NegativeValue {
+                offset: 4,
+                value: -1,
+            }),
+        ));
+    }
+
+    #[test]
+    fn test_import_scores() {
+        let temp = TempDir::new("import");
+        let root = temp.path();
+        let paths = Paths::for_test(root);
+        let date = Utc.timestamp_opt(1_000_000, 0).unwrap();
+
+        let prf = root.join(LegacyGame::TorusTrooper.pref_file());
+        fs::write(&prf, TORUS_TROOPER).unwrap();
+        let prefs = LegacyPrefs::load(LegacyGame::TorusTrooper, &prf).unwrap();
+
+        // Existing scores are kept.
+        let hard = ScoreTable::path(&paths, "normal", "hard").unwrap();
+        let mut table = ScoreTable::new(2);
+        table.insert(ScoreEntry::with_date("alice", 20000, 15, date));
+        table.save(&hard).unwrap();
+
+        let imported = prefs.import_scores(&paths, "player", date, 2).unwrap();
+        assert_eq!(
+            imported,
+            vec![
+                (
+                    ScoreTable::path(&paths, "normal", "normal").unwrap(),
+                    Some(1)
+                ),
+                (hard.clone(), Some(2)),
+            ],
+        );
+
+        let table = ScoreTable::load(&hard, 2).unwrap();
+        assert_eq!(
+            table.entries(),
+            &[
+                ScoreEntry::with_date("alice", 20000, 15, date),
+                ScoreEntry::with_date("player", 12340, 11, date),
+            ],
+        );
+
+        // Importing again, even at a later date, leaves the tables alone.
+        let normal = ScoreTable::path(&paths, "normal", "normal").unwrap();
+        let normal_table = ScoreTable::load(&normal, 2).unwrap();
+        let later = Utc.timestamp_opt(2_000_000, 0).unwrap();
+        let imported = prefs.import_scores(&paths, "player", later, 2).unwrap();
+        assert_eq!(imported, vec![(normal.clone(), None), (hard.clone(), None)],);
+        assert_eq!(ScoreTable::load(&normal, 2).unwrap(), normal_table);
+        assert_eq!(ScoreTable::load(&hard, 2).unwrap(), table);
+    }
+
+    #[test]
+    fn test_import_legacy_settings() {
+        let temp = TempDir::new("import-settings");
+        let paths = Paths::for_test(temp.path());
+        let prefs = LegacyPrefs::parse(LegacyGame::TorusTrooper, TORUS_TROOPER).unwrap();
+
+        // Settings which are not imported are kept.
+        let existing = GameSettings {
+            sfx_volume: 0.5,
+            ..GameSettings::default()
+        };
+        existing.save(&paths).unwrap();
+
+        let (settings, ignored) =
+            import_legacy_settings(&paths, Some(&prefs), vec!["-window", "-reverse"]).unwrap();
+        assert_eq!(ignored, vec!["-reverse"]);
+        assert_eq!(
+            settings,
+            GameSettings {
+                sfx_volume: 0.5,
+                windowed: true,
+                last_difficulty: Some("hard".into()),
+                last_stage: Some(6),
+                ..GameSettings::default()
+            },
+        );
+        assert_eq!(GameSettings::load(&paths).unwrap().0, settings);
+
+        // Settings from a newer version are not overwritten.
+        fs::write(GameSettings::path(&paths), "version = 2\n").unwrap();
+        assert!(matches!(
+            import_legacy_settings(&paths, None, Vec::<String>::new()),
+            Err(ImportError::Settings(_)),
+        ));
+        assert_eq!(
+            fs::read_to_string(GameSettings::path(&paths)).unwrap(),
+            "version = 2\n",
+        );
+    }
+
+    #[test]
+    fn test_import_legacy_options() {
+        let mut settings = GameSettings::default();
+        let ignored = import_legacy_options(
+            &mut settings,
+            vec![
+                "-brightness",
+                "80",
+                "-res",
+                "800",
+                "600",
+                "-window",
+                "-nosound",
+                "-reverse",
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(ignored, vec!["-reverse"]);
+        assert_eq!(settings.brightness, 0.8);
+        assert_eq!(settings.window_size, Vector2::new(800, 600));
+        assert!(settings.windowed);
+        assert!(!settings.music_enabled);
+        assert!(!settings.sfx_enabled);
+
+        assert!(import_legacy_options(&mut settings, vec!["-fullscreen"])
+            .unwrap()
+            .is_empty());
+        assert!(!settings.windowed);
+
+        import_legacy_options(&mut settings, vec!["-brightness", "120"]).unwrap();
+        assert_eq!(settings.brightness, 1.);
+        assert!(matches!(
+            import_legacy_options(&mut settings, vec!["-res", "800"]),
+            Err(ImportError::InvalidOption(_)),
+        ));
+    }
+}
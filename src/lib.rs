@@ -7,16 +7,39 @@
 
 #![warn(missing_docs)]
 
+mod assets;
+mod atomic;
+mod camera;
+mod collision;
+mod color;
+mod import;
 mod math;
+mod noise;
 mod paths;
 mod pool;
 mod rand;
+mod scores;
 mod sdl;
+mod settings;
 mod slice;
+mod steering;
+#[cfg(test)]
+mod test_util;
+mod tween;
 
 pub use crate::rand::*;
+pub use assets::*;
+pub use camera::*;
+pub use collision::*;
+pub use color::*;
+pub use import::*;
 pub use math::*;
+pub use noise::*;
 pub use paths::*;
 pub use pool::*;
+pub use scores::*;
 pub use sdl::*;
+pub use settings::*;
 pub use slice::*;
+pub use steering::*;
+pub use tween::*;
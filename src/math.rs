@@ -1,9 +1,17 @@
 // Distributed under the OSI-approved BSD 2-Clause License.
 // See accompanying LICENSE file for details.
 
-use std::ops::{Add, Sub};
+use std::f32::consts::PI;
+use std::ops::{Add, Neg, Rem, Sub};
 
-use cgmath::{One, Vector2};
+use cgmath::num_traits::Signed;
+use cgmath::{BaseNum, One, Vector2, Vector3, Zero};
+
+mod fixed;
+mod path;
+
+pub use self::fixed::*;
+pub use self::path::*;
 
 #[inline]
 /// Compute a Manhattan between a point and the origin.
@@ -31,6 +39,47 @@ pub fn fast_distance(v1: Vector2<f32>, v2: Vector2<f32>) -> f32 {
     }
 }
 
+#[inline]
+/// Compute a Manhattan distance between a point and the origin for any signed number type.
+pub(crate) fn fast_distance_origin_generic<T>(v: Vector2<T>) -> T
+where
+    T: BaseNum + Signed,
+{
+    let ax = v.x.abs();
+    let ay = v.y.abs();
+    let two = T::one() + T::one();
+
+    if ax < ay {
+        ay + ax / two
+    } else {
+        ax + ay / two
+    }
+}
+
+#[inline]
+/// Compute a Manhattan distance between a point and the origin in 3D.
+///
+/// This is the largest component plus half of each of the other two. It never underestimates the
+/// Euclidean distance and overestimates it by at most a factor of `sqrt(1.5)` (about 22%). The 2D
+/// variant overestimates by at most a factor of `sqrt(1.25)` (about 12%).
+pub fn fast_distance_origin3(v: Vector3<f32>) -> f32 {
+    let ax = v.x.abs();
+    let ay = v.y.abs();
+    let az = v.z.abs();
+
+    let max = ax.max(ay).max(az);
+
+    max + (ax + ay + az - max) / 2.
+}
+
+#[inline]
+/// Compute a Manhattan distance between two points in 3D.
+///
+/// See `fast_distance_origin3` for the error bounds.
+pub fn fast_distance3(v1: Vector3<f32>, v2: Vector3<f32>) -> f32 {
+    fast_distance_origin3(v1 - v2)
+}
+
 #[inline]
 /// Return `true` if a value is between two bounds (inclusive).
 fn between<T>(low: T, expect: T, high: T) -> bool
@@ -52,20 +101,35 @@ pub fn wrap_inc<T>(value: T, max: T) -> T
 where
     T: PartialOrd + One + Add<T, Output = T> + Sub<T, Output = T> + Copy,
 {
-    wrap_inc_by(value, max, T::one())
+    let new_value = value + T::one();
+    if new_value >= max {
+        new_value - max
+    } else {
+        new_value
+    }
 }
 
 #[inline]
 /// Increment a value by a given step around within a range.
+///
+/// The value must be within `[0, max)`. The step may be negative or larger than the range.
+///
+/// # Panics
+///
+/// Panics if `max` is not positive.
 pub fn wrap_inc_by<T>(value: T, max: T, step: T) -> T
 where
-    T: PartialOrd + Add<T, Output = T> + Sub<T, Output = T> + Copy,
+    T: PartialOrd + Zero + Add<T, Output = T> + Sub<T, Output = T> + Rem<T, Output = T> + Copy,
 {
-    let new_value = value + step;
-    if new_value >= max {
-        new_value - max
+    check_wrap_max(max);
+    let step = step % max;
+    if step < T::zero() {
+        wrap_dec_by(value, max, T::zero() - step)
+    } else if value >= max - step {
+        // Compare before adding so that values near the top of the type do not overflow.
+        value - (max - step)
     } else {
-        new_value
+        value + step
     }
 }
 
@@ -75,25 +139,356 @@ pub fn wrap_dec<T>(value: T, max: T) -> T
 where
     T: PartialOrd + One + Add<T, Output = T> + Sub<T, Output = T> + Copy,
 {
-    wrap_dec_by(value, max, T::one())
+    let one = T::one();
+    if value < one {
+        value + max - one
+    } else {
+        value - one
+    }
 }
 
 #[inline]
 /// Decrement a value by a given step around within a range.
+///
+/// The value must be within `[0, max)`. The step may be negative or larger than the range.
+///
+/// # Panics
+///
+/// Panics if `max` is not positive.
 pub fn wrap_dec_by<T>(value: T, max: T, step: T) -> T
 where
-    T: PartialOrd + Add<T, Output = T> + Sub<T, Output = T> + Copy,
+    T: PartialOrd + Zero + Add<T, Output = T> + Sub<T, Output = T> + Rem<T, Output = T> + Copy,
 {
-    if value < step {
-        value + max - step
+    check_wrap_max(max);
+    let step = step % max;
+    if step < T::zero() {
+        wrap_inc_by(value, max, T::zero() - step)
+    } else if value < step {
+        value + (max - step)
     } else {
         value - step
     }
 }
 
+#[inline]
+/// Check that the size of a wrapping range is positive.
+fn check_wrap_max<T>(max: T)
+where
+    T: PartialOrd + Zero,
+{
+    assert!(max > T::zero(), "the size of a wrapping range must be positive");
+}
+
+#[inline]
+/// Wrap a value into the range `[0, max)`.
+///
+/// # Panics
+///
+/// Panics if `max` is not positive.
+pub fn wrap<T>(value: T, max: T) -> T
+where
+    T: PartialOrd + Zero + Add<T, Output = T> + Rem<T, Output = T> + Copy,
+{
+    check_wrap_max(max);
+    let rem = value % max;
+    if rem < T::zero() {
+        // The sum is below `max`, so this cannot overflow. The remainder handles rounding of
+        // floating point values onto `max`.
+        (rem + max) % max
+    } else {
+        rem
+    }
+}
+
+#[inline]
+/// Wrap a vector into the rectangle from the origin to `size`.
+pub fn wrap_vector<T>(v: Vector2<T>, size: Vector2<T>) -> Vector2<T>
+where
+    T: PartialOrd + Zero + Add<T, Output = T> + Rem<T, Output = T> + Copy,
+{
+    Vector2::new(wrap(v.x, size.x), wrap(v.y, size.y))
+}
+
+#[inline]
+/// The shortest displacement from `from` to `to` within a wrapping range of size `max`.
+///
+/// The result is within `[-max / 2, max / 2]`.
+fn wrapped_delta<T>(from: T, to: T, max: T) -> T
+where
+    T: PartialOrd
+        + Zero
+        + Neg<Output = T>
+        + Add<T, Output = T>
+        + Sub<T, Output = T>
+        + Rem<T, Output = T>
+        + Copy,
+{
+    let delta = wrap(to - from, max);
+    if delta > max - delta {
+        delta - max
+    } else {
+        delta
+    }
+}
+
+#[inline]
+/// The shortest displacement from `from` to `to` on a wrapping field of the given size.
+///
+/// Displacements may be negative, so this is only available for signed types.
+pub fn wrapped_displacement<T>(from: Vector2<T>, to: Vector2<T>, size: Vector2<T>) -> Vector2<T>
+where
+    T: PartialOrd
+        + Zero
+        + Neg<Output = T>
+        + Add<T, Output = T>
+        + Sub<T, Output = T>
+        + Rem<T, Output = T>
+        + Copy,
+{
+    Vector2::new(
+        wrapped_delta(from.x, to.x, size.x),
+        wrapped_delta(from.y, to.y, size.y),
+    )
+}
+
+#[inline]
+/// Compute a Manhattan distance between two points on a wrapping field of the given size.
+pub fn wrapped_fast_distance<T>(v1: Vector2<T>, v2: Vector2<T>, size: Vector2<T>) -> T
+where
+    T: BaseNum + Signed,
+{
+    fast_distance_origin_generic(wrapped_displacement(v1, v2, size))
+}
+
+#[inline]
+/// Normalize an angle into the range `[-π, π)`.
+pub fn normalize_angle(angle: f32) -> f32 {
+    let angle = (angle + PI).rem_euclid(2. * PI) - PI;
+
+    // Rounding may land exactly on the upper bound.
+    if angle >= PI {
+        -PI
+    } else {
+        angle
+    }
+}
+
+#[inline]
+/// The shortest signed angle which rotates `from` to `to`.
+///
+/// The result is in the range `[-π, π)`.
+pub fn angle_difference(from: f32, to: f32) -> f32 {
+    normalize_angle(to - from)
+}
+
+#[inline]
+/// Rotate an angle toward a target by at most `max_turn`.
+///
+/// The rotation takes the shortest direction. The result is normalized.
+pub fn rotate_toward(angle: f32, target: f32, max_turn: f32) -> f32 {
+    let diff = angle_difference(angle, target);
+
+    if diff.abs() <= max_turn {
+        normalize_angle(target)
+    } else {
+        normalize_angle(angle + max_turn.copysign(diff))
+    }
+}
+
+#[inline]
+/// The unit direction vector for an angle.
+///
+/// Angles are measured from the positive y axis toward the positive x axis, as in the original
+/// games.
+pub fn angle_to_vector(angle: f32) -> Vector2<f32> {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(sin, cos)
+}
+
+#[inline]
+/// The angle of a direction vector.
+///
+/// This is the inverse of `angle_to_vector`. The result is in the range `[-π, π]`.
+pub fn vector_to_angle(v: Vector2<f32>) -> f32 {
+    v.x.atan2(v.y)
+}
+
+/// A lookup table for sine and cosine of integer angles.
+///
+/// The original games use precomputed tables indexed by integer angles where a full turn is
+/// divided into a fixed number of units (e.g., 360 or 1024). Bullet patterns depend on the exact
+/// values in these tables, so they are reproduced here. Each sine is computed as
+/// `sin(2π * i / resolution)` in double precision and then rounded to single precision. Like the
+/// originals, cosines are the sines a quarter turn further along.
+///
+/// Angles follow the same convention as `angle_to_vector`.
+#[derive(Debug, Clone)]
+pub struct SinCosTable {
+    /// The sine of each angle.
+    sin: Vec<f32>,
+    /// The cosine of each angle.
+    cos: Vec<f32>,
+    /// The number of angle units in a full turn.
+    resolution: u32,
+}
+
+impl SinCosTable {
+    /// Create a table with `resolution` angle units in a full turn.
+    ///
+    /// Panics if the resolution is zero.
+    pub fn new(resolution: u32) -> Self {
+        assert!(
+            resolution > 0,
+            "the resolution of a sin/cos table must be positive",
+        );
+
+        let sin_of =
+            |units: f64| (units * std::f64::consts::PI * 2. / f64::from(resolution)).sin() as f32;
+        // When the resolution is a multiple of 4, the quarter turn is exact and this matches
+        // looking up the sine table at an offset.
+        let quarter = f64::from(resolution) / 4.;
+
+        SinCosTable {
+            sin: (0..resolution).map(|i| sin_of(f64::from(i))).collect(),
+            cos: (0..resolution)
+                .map(|i| sin_of(f64::from(i) + quarter))
+                .collect(),
+            resolution,
+        }
+    }
+
+    #[inline]
+    /// The number of angle units in a full turn.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    #[inline]
+    fn index(&self, angle: i32) -> usize {
+        i64::from(angle).rem_euclid(i64::from(self.resolution)) as usize
+    }
+
+    #[inline]
+    /// The sine of an angle.
+    ///
+    /// Angles outside of a single turn wrap around.
+    pub fn sin(&self, angle: i32) -> f32 {
+        self.sin[self.index(angle)]
+    }
+
+    #[inline]
+    /// The cosine of an angle.
+    ///
+    /// Angles outside of a single turn wrap around.
+    pub fn cos(&self, angle: i32) -> f32 {
+        self.cos[self.index(angle)]
+    }
+
+    #[inline]
+    /// The unit direction vector for an angle.
+    pub fn direction(&self, angle: i32) -> Vector2<f32> {
+        let index = self.index(angle);
+        Vector2::new(self.sin[index], self.cos[index])
+    }
+
+    #[inline]
+    /// Convert an angle in table units into radians.
+    pub fn to_radians(&self, angle: i32) -> f32 {
+        (f64::from(angle) * std::f64::consts::PI * 2. / f64::from(self.resolution)) as f32
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::math::{wrap_dec, wrap_dec_by, wrap_inc, wrap_inc_by};
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use cgmath::{InnerSpace, Vector2, Vector3};
+
+    use crate::math::{
+        angle_difference, angle_to_vector, fast_distance, fast_distance3, fast_distance_origin,
+        fast_distance_origin3, normalize_angle, rotate_toward, vector_to_angle, wrap, wrap_dec,
+        wrap_dec_by, wrap_inc, wrap_inc_by, wrap_vector, wrapped_displacement,
+        wrapped_fast_distance, SinCosTable,
+    };
+    use crate::rand::Rand;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{} != {}",
+            actual,
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_fast_distance_bounds() {
+        let mut rand: Rand = Rand::with_seed(1);
+
+        (0..10000).for_each(|_| {
+            let v1 = Vector2::new(rand.next_float_signed(100.), rand.next_float_signed(100.));
+            let v2 = Vector2::new(rand.next_float_signed(100.), rand.next_float_signed(100.));
+            let exact = (v1 - v2).magnitude();
+            let fast = fast_distance(v1, v2);
+
+            assert_eq!(fast, fast_distance_origin(v1 - v2));
+            assert!(exact <= fast * (1. + 1e-6), "{} < {}", fast, exact);
+            assert!(
+                fast <= exact * 1.25_f32.sqrt() * (1. + 1e-6),
+                "{} > {}",
+                fast,
+                exact
+            );
+        });
+    }
+
+    #[test]
+    fn test_fast_distance3_bounds() {
+        let mut rand: Rand = Rand::with_seed(1);
+
+        (0..10000).for_each(|_| {
+            let v1 = Vector3::new(
+                rand.next_float_signed(100.),
+                rand.next_float_signed(100.),
+                rand.next_float_signed(100.),
+            );
+            let v2 = Vector3::new(
+                rand.next_float_signed(100.),
+                rand.next_float_signed(100.),
+                rand.next_float_signed(100.),
+            );
+            let exact = (v1 - v2).magnitude();
+            let fast = fast_distance3(v1, v2);
+
+            assert_eq!(fast, fast_distance_origin3(v1 - v2));
+            assert!(exact <= fast * (1. + 1e-6), "{} < {}", fast, exact);
+            assert!(
+                fast <= exact * 1.5_f32.sqrt() * (1. + 1e-6),
+                "{} > {}",
+                fast,
+                exact
+            );
+        });
+    }
+
+    #[test]
+    fn test_fast_distance3() {
+        assert_eq!(fast_distance_origin3(Vector3::new(0., 0., 0.)), 0.);
+        assert_eq!(fast_distance_origin3(Vector3::new(0., -3., 0.)), 3.);
+        assert_eq!(fast_distance_origin3(Vector3::new(2., -4., 2.)), 6.);
+        assert_eq!(
+            fast_distance3(Vector3::new(1., 1., 1.), Vector3::new(1., 1., 5.)),
+            4.,
+        );
+        // The worst case is exactly at the bound.
+        let worst = Vector3::new(1., 0.5, 0.5);
+        assert!((fast_distance_origin3(worst) / worst.magnitude() - 1.5_f32.sqrt()).abs() < 1e-6);
+        // With one component zero, it matches the 2D variant.
+        assert_eq!(
+            fast_distance_origin3(Vector3::new(3., 0., -1.)),
+            fast_distance_origin(Vector2::new(3., -1.)),
+        );
+    }
 
     #[test]
     fn test_wrap_inc() {
@@ -122,4 +517,270 @@ mod test {
         assert_eq!(wrap_dec_by(1, 3, 2), 2);
         assert_eq!(wrap_dec_by(1, 4, 2), 3);
     }
+
+    #[test]
+    fn test_wrap_inc_by_large_and_negative() {
+        assert_eq!(wrap_inc_by(1, 4, 6), 3);
+        assert_eq!(wrap_inc_by(1, 4, 9), 2);
+        assert_eq!(wrap_inc_by(1, 4, -2), 3);
+        assert_eq!(wrap_inc_by(1, 4, -7), 2);
+        assert_eq!(wrap_inc_by(1u32, 4, 9), 2);
+        assert_eq!(wrap_inc_by(3.5_f32, 4., 9.), 0.5);
+        assert_eq!(wrap_inc_by(0.5_f32, 4., -1.), 3.5);
+        // Values near the top of the type do not overflow.
+        assert_eq!(wrap_inc_by(250u8, 255, 10), 5);
+        assert_eq!(wrap_inc_by(254u8, 255, 254), 253);
+        assert_eq!(wrap_inc_by(120i8, 127, -126), 121);
+        assert_eq!(wrap_inc_by(1i8, 127, i8::MIN), 0);
+        assert_eq!(wrap_inc(254u8, 255), 0);
+    }
+
+    #[test]
+    fn test_wrap_dec_by_large_and_negative() {
+        assert_eq!(wrap_dec_by(1, 4, 6), 3);
+        assert_eq!(wrap_dec_by(1, 4, 9), 0);
+        assert_eq!(wrap_dec_by(1, 4, -2), 3);
+        assert_eq!(wrap_dec_by(1, 4, -7), 0);
+        assert_eq!(wrap_dec_by(1u32, 4, 6), 3);
+        assert_eq!(wrap_dec_by(1u8, 200, 250), 151);
+        assert_eq!(wrap_dec_by(0.5_f32, 4., 9.), 3.5);
+        assert_eq!(wrap_dec_by(3.5_f32, 4., -1.), 0.5);
+        assert_eq!(wrap_dec_by(5u8, 255, 10), 250);
+        assert_eq!(wrap_dec_by(120i8, 127, -10), 3);
+        assert_eq!(wrap_dec(0u8, 255), 254);
+    }
+
+    #[test]
+    #[should_panic(expected = "the size of a wrapping range must be positive")]
+    fn test_wrap_inc_by_empty_range() {
+        wrap_inc_by(0, 0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "the size of a wrapping range must be positive")]
+    fn test_wrap_dec_by_empty_range() {
+        wrap_dec_by(0u32, 0, 1);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap(5, 4), 1);
+        assert_eq!(wrap(-1, 4), 3);
+        assert_eq!(wrap(-9, 4), 3);
+        assert_eq!(wrap(0, 4), 0);
+        assert_eq!(wrap(9u32, 4), 1);
+        assert_eq!(wrap(-0.5_f32, 4.), 3.5);
+        assert_eq!(wrap(10.5_f32, 4.), 2.5);
+        // Tiny negative values must not round up to the upper bound.
+        let tiny = wrap(-1e-9_f32, 4.);
+        assert!((0. ..4.).contains(&tiny), "{}", tiny);
+        // Ranges larger than half of the type do not overflow.
+        assert_eq!(wrap(-1i8, 100), 99);
+        assert_eq!(wrap(-100i8, 120), 20);
+        assert_eq!(wrap(i8::MIN, 127), 126);
+        assert_eq!(wrap(200u8, 250), 200);
+    }
+
+    #[test]
+    fn test_wrap_vector() {
+        let size = Vector2::new(10_f32, 20.);
+
+        assert_eq!(
+            wrap_vector(Vector2::new(12., -5.), size),
+            Vector2::new(2., 15.),
+        );
+        assert_eq!(
+            wrap_vector(Vector2::new(-1, 41), Vector2::new(10, 20)),
+            Vector2::new(9, 1),
+        );
+    }
+
+    #[test]
+    fn test_wrapped_displacement() {
+        let size = Vector2::new(10_f32, 20.);
+
+        assert_eq!(
+            wrapped_displacement(Vector2::new(1., 1.), Vector2::new(3., 4.), size),
+            Vector2::new(2., 3.),
+        );
+        assert_eq!(
+            wrapped_displacement(Vector2::new(1., 1.), Vector2::new(9., 19.), size),
+            Vector2::new(-2., -2.),
+        );
+        assert_eq!(
+            wrapped_displacement(Vector2::new(9., 19.), Vector2::new(1., 1.), size),
+            Vector2::new(2., 2.),
+        );
+        assert_eq!(
+            wrapped_displacement(Vector2::new(0, 0), Vector2::new(7, 3), Vector2::new(10, 20)),
+            Vector2::new(-3, 3),
+        );
+        assert_eq!(
+            wrapped_fast_distance(Vector2::new(1., 1.), Vector2::new(9., 19.), size),
+            3.,
+        );
+        // Fields larger than half of the type do not overflow.
+        assert_eq!(
+            wrapped_displacement(
+                Vector2::new(0i8, 10),
+                Vector2::new(100, 110),
+                Vector2::new(120, 120)
+            ),
+            Vector2::new(-20, 100 - 120),
+        );
+        assert_eq!(
+            wrapped_displacement(
+                Vector2::new(0i8, 0),
+                Vector2::new(50, 70),
+                Vector2::new(120, 120)
+            ),
+            Vector2::new(50, -50),
+        );
+    }
+
+    #[test]
+    fn test_normalize_angle() {
+        assert_close(normalize_angle(0.), 0.);
+        assert_close(normalize_angle(FRAC_PI_2), FRAC_PI_2);
+        assert_close(normalize_angle(PI), -PI);
+        assert_close(normalize_angle(-PI), -PI);
+        assert_close(normalize_angle(3. * FRAC_PI_2), -FRAC_PI_2);
+        assert_close(normalize_angle(-3. * FRAC_PI_2), FRAC_PI_2);
+        assert_close(normalize_angle(10. * PI + 1.), 1.);
+        assert_close(normalize_angle(-10. * PI - 1.), -1.);
+
+        (-1000..1000).map(|i| (i as f32) * 0.037).for_each(|a| {
+            let n = normalize_angle(a);
+            assert!((-PI..PI).contains(&n), "{} -> {}", a, n);
+        });
+    }
+
+    #[test]
+    fn test_angle_difference() {
+        assert_close(angle_difference(0., FRAC_PI_2), FRAC_PI_2);
+        assert_close(angle_difference(FRAC_PI_2, 0.), -FRAC_PI_2);
+        // The shortest way crosses the discontinuity.
+        assert_close(angle_difference(PI - 0.1, -PI + 0.1), 0.2);
+        assert_close(angle_difference(-PI + 0.1, PI - 0.1), -0.2);
+        assert_close(angle_difference(0.5, 0.5 + 4. * PI), 0.);
+    }
+
+    #[test]
+    fn test_rotate_toward() {
+        assert_close(rotate_toward(0., FRAC_PI_2, 0.1), 0.1);
+        assert_close(rotate_toward(0., -FRAC_PI_2, 0.1), -0.1);
+        assert_close(rotate_toward(0., 0.05, 0.1), 0.05);
+        assert_close(rotate_toward(PI - 0.05, -PI + 0.05, 0.2), -PI + 0.05);
+        assert_close(rotate_toward(PI - 0.05, -PI + 0.15, 0.1), -PI + 0.05);
+        assert_close(rotate_toward(-PI + 0.05, PI - 0.15, 0.1), PI - 0.05);
+    }
+
+    #[test]
+    fn test_angle_vectors() {
+        let assert_vec = |actual: Vector2<f32>, expected: Vector2<f32>| {
+            assert_close(actual.x, expected.x);
+            assert_close(actual.y, expected.y);
+        };
+
+        assert_vec(angle_to_vector(0.), Vector2::new(0., 1.));
+        assert_vec(angle_to_vector(FRAC_PI_2), Vector2::new(1., 0.));
+        assert_vec(angle_to_vector(-FRAC_PI_2), Vector2::new(-1., 0.));
+        assert_close(vector_to_angle(Vector2::new(0., 1.)), 0.);
+        assert_close(vector_to_angle(Vector2::new(1., 0.)), FRAC_PI_2);
+        assert_close(vector_to_angle(Vector2::new(2., 2.)), FRAC_PI_4);
+
+        (-100..100).map(|i| (i as f32) * 0.031).for_each(|a| {
+            assert_close(vector_to_angle(angle_to_vector(a) * 3.), a);
+        });
+    }
+
+    #[test]
+    fn test_sin_cos_table_original_values() {
+        // Noiz2sa and rRootage use an integer table of 1024 angles scaled by 256, with cosines a
+        // quarter turn further along: `sctbl[i] = (int)(sin(i * M_PI / 512) * 256)`.
+        let sctbl = |i: i32| (f64::from(i) * std::f64::consts::PI / 512.).sin() * 256.;
+        let table = SinCosTable::new(1024);
+
+        (0..1024).for_each(|angle| {
+            assert_eq!(
+                (table.sin(angle) * 256.) as i32,
+                sctbl(angle) as i32,
+                "sin({})",
+                angle,
+            );
+            assert_eq!(
+                (table.cos(angle) * 256.) as i32,
+                sctbl(angle + 256) as i32,
+                "cos({})",
+                angle,
+            );
+        });
+
+        // Single precision values of the table.
+        assert_eq!(table.sin(128), 0.707_106_77);
+        assert_eq!(table.sin(85), 0.498_227_66);
+        assert_eq!(table.sin(1), 0.006_135_884_7);
+        assert_eq!(table.cos(128), table.sin(384));
+    }
+
+    #[test]
+    fn test_sin_cos_table_any_resolution() {
+        [1, 3, 90, 360, 1000].iter().for_each(|&resolution| {
+            let table = SinCosTable::new(resolution);
+            assert_eq!(table.resolution(), resolution);
+
+            (0..resolution as i32).for_each(|i| {
+                let radians = table.to_radians(i);
+                assert_close(table.sin(i), radians.sin());
+                assert_close(table.cos(i), radians.cos());
+            })
+        });
+
+        // With a whole quarter turn, cosines are exactly the sines a quarter turn along. The last
+        // quarter turn is not wrapped, as in the original tables.
+        let table = SinCosTable::new(360);
+        (0..270).for_each(|i| assert_eq!(table.cos(i).to_bits(), table.sin(i + 90).to_bits()));
+    }
+
+    #[test]
+    fn test_sin_cos_table_values() {
+        let table = SinCosTable::new(1024);
+
+        assert_eq!(table.sin(0), 0.);
+        assert_eq!(table.sin(256), 1.);
+        assert_eq!(table.cos(0), 1.);
+        assert_close(table.cos(256), 0.);
+        assert_close(table.sin(512), 0.);
+        assert_eq!(table.sin(768), -1.);
+        assert_eq!(table.cos(512), -1.);
+
+        (0..1024).for_each(|i| {
+            let radians = table.to_radians(i);
+            assert_close(table.sin(i), radians.sin());
+            assert_close(table.cos(i), radians.cos());
+            assert_eq!(table.direction(i), Vector2::new(table.sin(i), table.cos(i)));
+            let v = angle_to_vector(radians);
+            assert_close(table.direction(i).x, v.x);
+            assert_close(table.direction(i).y, v.y);
+        });
+    }
+
+    #[test]
+    fn test_sin_cos_table_wraps() {
+        let table = SinCosTable::new(360);
+
+        (-720..720).for_each(|i: i32| {
+            let wrapped = i.rem_euclid(360);
+            assert_eq!(table.sin(i), table.sin(wrapped));
+            assert_eq!(table.cos(i), table.cos(wrapped));
+        });
+        assert_eq!(table.sin(i32::MIN), table.sin(i32::MIN.rem_euclid(360)));
+        assert_eq!(table.cos(i32::MAX), table.cos(i32::MAX.rem_euclid(360)));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be positive")]
+    fn test_sin_cos_table_bad_resolution() {
+        SinCosTable::new(0);
+    }
 }
@@ -0,0 +1,592 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Fixed-point numbers
+//!
+//! Floating point results may differ between optimization levels and processors. Simulations
+//! which must be reproduced exactly, such as those driving replays, may instead run on these
+//! fixed-point numbers and convert to floating point only for rendering.
+
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
+use std::str::FromStr;
+
+use cgmath::num_traits::{self, Num, One, Signed, ToPrimitive, Zero};
+use cgmath::Vector2;
+use thiserror::Error;
+
+/// A signed 16.16 fixed-point number.
+///
+/// Arithmetic is performed on integers and so gives the same results everywhere. Unlike integer
+/// arithmetic, overflow panics in all builds so that debug and release builds agree; use the
+/// `checked_*` methods where overflow is expected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(i32);
+
+/// A vector of fixed-point numbers.
+pub type FixedVector2 = Vector2<Fixed>;
+
+/// An error parsing a fixed-point number.
+#[derive(Debug, Error)]
+#[error("invalid fixed-point number: {:?}", _0)]
+pub struct ParseFixedError(String);
+
+impl Fixed {
+    /// The number of fractional bits.
+    pub const FRAC_BITS: u32 = 16;
+    /// Zero.
+    pub const ZERO: Self = Fixed(0);
+    /// One.
+    pub const ONE: Self = Fixed(1 << Self::FRAC_BITS);
+    /// The smallest positive value.
+    pub const EPSILON: Self = Fixed(1);
+    /// The smallest value.
+    pub const MIN: Self = Fixed(i32::MIN);
+    /// The largest value.
+    pub const MAX: Self = Fixed(i32::MAX);
+
+    #[inline]
+    /// Create a fixed-point number from its underlying representation.
+    pub const fn from_bits(bits: i32) -> Self {
+        Fixed(bits)
+    }
+
+    #[inline]
+    /// The underlying representation of the number.
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    #[inline]
+    /// Create a fixed-point number from an integer.
+    pub const fn from_int(value: i16) -> Self {
+        Fixed((value as i32) << Self::FRAC_BITS)
+    }
+
+    #[inline]
+    /// Create a fixed-point number from a float, rounding to the nearest representable value.
+    ///
+    /// Values out of range saturate.
+    pub fn from_f32(value: f32) -> Self {
+        Self::from_f64(f64::from(value))
+    }
+
+    #[inline]
+    /// Create a fixed-point number from a float, rounding to the nearest representable value.
+    ///
+    /// Values out of range saturate.
+    pub fn from_f64(value: f64) -> Self {
+        Fixed((value * f64::from(Self::ONE.0)).round() as i32)
+    }
+
+    /// Create a fixed-point number from a float, rounding to the nearest representable value.
+    ///
+    /// Returns `None` if the value is out of range.
+    pub fn checked_from_f64(value: f64) -> Option<Self> {
+        let bits = (value * f64::from(Self::ONE.0)).round();
+        if f64::from(i32::MIN) <= bits && bits <= f64::from(i32::MAX) {
+            Some(Fixed(bits as i32))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    /// Convert the number to a float.
+    pub fn to_f32(self) -> f32 {
+        self.to_f64() as f32
+    }
+
+    #[inline]
+    /// Convert the number to a float.
+    pub fn to_f64(self) -> f64 {
+        f64::from(self.0) / f64::from(Self::ONE.0)
+    }
+
+    #[inline]
+    /// The largest integer less than or equal to the number.
+    pub fn floor(self) -> i32 {
+        self.0 >> Self::FRAC_BITS
+    }
+
+    #[inline]
+    /// The fractional part of the number; always non-negative.
+    pub fn fract(self) -> Self {
+        Fixed(self.0 & (Self::ONE.0 - 1))
+    }
+
+    #[inline]
+    /// The absolute value of the number.
+    ///
+    /// Panics for `Fixed::MIN`.
+    pub fn abs(self) -> Self {
+        Fixed(
+            self.0
+                .checked_abs()
+                .expect("fixed-point absolute value overflowed"),
+        )
+    }
+
+    #[inline]
+    /// Add two numbers, returning `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Fixed)
+    }
+
+    #[inline]
+    /// Subtract two numbers, returning `None` on overflow.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Fixed)
+    }
+
+    #[inline]
+    /// Multiply two numbers, returning `None` on overflow.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let product = (i64::from(self.0) * i64::from(rhs.0)) >> Self::FRAC_BITS;
+        i32::try_from(product).ok().map(Fixed)
+    }
+
+    #[inline]
+    /// Divide two numbers, returning `None` on overflow or division by zero.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.0 == 0 {
+            return None;
+        }
+
+        let quotient = (i64::from(self.0) << Self::FRAC_BITS) / i64::from(rhs.0);
+        i32::try_from(quotient).ok().map(Fixed)
+    }
+
+    #[inline]
+    /// The remainder of dividing two numbers, returning `None` on overflow or division by zero.
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        self.0.checked_rem(rhs.0).map(Fixed)
+    }
+
+    #[inline]
+    /// Negate the number, returning `None` on overflow.
+    pub fn checked_neg(self) -> Option<Self> {
+        self.0.checked_neg().map(Fixed)
+    }
+
+    /// The square root of the number, rounded down.
+    ///
+    /// Returns `None` for negative numbers.
+    pub fn sqrt(self) -> Option<Self> {
+        if self.0 < 0 {
+            return None;
+        }
+
+        // sqrt(x / 2^16) * 2^16 == sqrt(x * 2^16)
+        let square = (self.0 as u64) << Self::FRAC_BITS;
+        let mut root = (square as f64).sqrt() as u64;
+        // Correct any rounding in the float estimate.
+        while root * root > square {
+            root -= 1;
+        }
+        while (root + 1) * (root + 1) <= square {
+            root += 1;
+        }
+
+        Some(Fixed(root as i32))
+    }
+}
+
+impl From<i16> for Fixed {
+    fn from(value: i16) -> Self {
+        Self::from_int(value)
+    }
+}
+
+impl From<Fixed> for f32 {
+    fn from(value: Fixed) -> Self {
+        value.to_f32()
+    }
+}
+
+impl From<Fixed> for f64 {
+    fn from(value: Fixed) -> Self {
+        value.to_f64()
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&Fixed::to_f64(*self), f)
+    }
+}
+
+impl FromStr for Fixed {
+    type Err = ParseFixedError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_radix(s, 10)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs)
+            .expect("fixed-point addition overflowed")
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs)
+            .expect("fixed-point subtraction overflowed")
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs)
+            .expect("fixed-point multiplication overflowed")
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: Self) -> Self {
+        self.checked_div(rhs)
+            .expect("fixed-point division overflowed")
+    }
+}
+
+impl Rem for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn rem(self, rhs: Self) -> Self {
+        self.checked_rem(rhs)
+            .expect("fixed-point remainder by zero")
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self {
+        self.checked_neg().expect("fixed-point negation overflowed")
+    }
+}
+
+macro_rules! impl_assign_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for Fixed {
+            #[inline]
+            fn $method(&mut self, rhs: Self) {
+                *self = *self $op rhs;
+            }
+        }
+    };
+}
+
+impl_assign_op!(AddAssign, add_assign, +);
+impl_assign_op!(SubAssign, sub_assign, -);
+impl_assign_op!(MulAssign, mul_assign, *);
+impl_assign_op!(DivAssign, div_assign, /);
+impl_assign_op!(RemAssign, rem_assign, %);
+
+impl Zero for Fixed {
+    #[inline]
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl One for Fixed {
+    #[inline]
+    fn one() -> Self {
+        Self::ONE
+    }
+}
+
+impl Num for Fixed {
+    type FromStrRadixErr = ParseFixedError;
+
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let invalid = || ParseFixedError(s.into());
+
+        if radix == 10 {
+            return s
+                .parse::<f64>()
+                .ok()
+                .and_then(Self::checked_from_f64)
+                .ok_or_else(invalid);
+        }
+
+        if !(2..=36).contains(&radix) {
+            return Err(invalid());
+        }
+
+        let (negative, digits) = if let Some(digits) = s.strip_prefix('-') {
+            (true, digits)
+        } else {
+            (false, s.strip_prefix('+').unwrap_or(s))
+        };
+        let (int_digits, frac_digits) = if let Some(dot) = digits.find('.') {
+            (&digits[..dot], &digits[dot + 1..])
+        } else {
+            (digits, "")
+        };
+        if int_digits.is_empty() && frac_digits.is_empty() {
+            return Err(invalid());
+        }
+
+        let digit = |c: char| c.to_digit(radix).map(f64::from).ok_or_else(invalid);
+        let radix = f64::from(radix);
+        let int_part = int_digits
+            .chars()
+            .try_fold(0., |acc, c| Ok(acc * radix + digit(c)?))?;
+        let frac_part = frac_digits
+            .chars()
+            .rev()
+            .try_fold(0., |acc, c| Ok((acc + digit(c)?) / radix))?;
+
+        let value = int_part + frac_part;
+        Self::checked_from_f64(if negative { -value } else { value }).ok_or_else(invalid)
+    }
+}
+
+impl Signed for Fixed {
+    #[inline]
+    fn abs(&self) -> Self {
+        Fixed::abs(*self)
+    }
+
+    #[inline]
+    fn abs_sub(&self, other: &Self) -> Self {
+        if *self <= *other {
+            Self::ZERO
+        } else {
+            *self - *other
+        }
+    }
+
+    #[inline]
+    fn signum(&self) -> Self {
+        Fixed(self.0.signum() << Self::FRAC_BITS)
+    }
+
+    #[inline]
+    fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    #[inline]
+    fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl ToPrimitive for Fixed {
+    // Like float conversions, these truncate toward zero.
+    fn to_i64(&self) -> Option<i64> {
+        Some(i64::from(self.0 / Self::ONE.0))
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        u64::try_from(self.0 / Self::ONE.0).ok()
+    }
+
+    fn to_f32(&self) -> Option<f32> {
+        Some(Fixed::to_f32(*self))
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(Fixed::to_f64(*self))
+    }
+}
+
+impl num_traits::NumCast for Fixed {
+    fn from<T>(n: T) -> Option<Self>
+    where
+        T: ToPrimitive,
+    {
+        n.to_f64().and_then(Fixed::checked_from_f64)
+    }
+}
+
+#[inline]
+/// Convert a float vector to fixed point.
+pub fn to_fixed_vector(v: Vector2<f32>) -> FixedVector2 {
+    Vector2::new(Fixed::from_f32(v.x), Fixed::from_f32(v.y))
+}
+
+#[inline]
+/// Compute a Manhattan distance between a fixed-point vector and the origin.
+pub fn fast_distance_origin_fixed(v: FixedVector2) -> Fixed {
+    super::fast_distance_origin_generic(v)
+}
+
+#[inline]
+/// Compute a Manhattan distance between two fixed-point vectors.
+pub fn fast_distance_fixed(v1: FixedVector2, v2: FixedVector2) -> Fixed {
+    fast_distance_origin_fixed(v1 - v2)
+}
+
+#[inline]
+/// Convert a fixed-point vector to floats for rendering.
+pub fn to_float_vector(v: FixedVector2) -> Vector2<f32> {
+    Vector2::new(v.x.to_f32(), v.y.to_f32())
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::num_traits::{Num, ToPrimitive};
+    use cgmath::Vector2;
+
+    use crate::math::{
+        fast_distance_fixed, fast_distance_origin_fixed, to_fixed_vector, to_float_vector,
+        wrap_dec, wrap_inc, wrap_inc_by, wrapped_displacement, wrapped_fast_distance, Fixed,
+    };
+
+    fn fixed(value: f32) -> Fixed {
+        Fixed::from_f32(value)
+    }
+
+    #[test]
+    fn test_fixed_conversions() {
+        assert_eq!(Fixed::from_int(3).to_bits(), 3 << 16);
+        assert_eq!(Fixed::from(-2_i16), fixed(-2.));
+        assert_eq!(fixed(1.5).to_f32(), 1.5);
+        assert_eq!(f32::from(fixed(-0.25)), -0.25);
+        assert_eq!(Fixed::EPSILON.to_f64(), 1. / 65536.);
+        assert_eq!(fixed(1e10), Fixed::MAX);
+        assert_eq!(fixed(-1e10), Fixed::MIN);
+
+        assert_eq!(fixed(2.75).floor(), 2);
+        assert_eq!(fixed(-2.25).floor(), -3);
+        assert_eq!(fixed(-2.25).fract(), fixed(0.75));
+
+        assert_eq!("1.25".parse::<Fixed>().unwrap(), fixed(1.25));
+        assert_eq!(
+            "nope".parse::<Fixed>().unwrap_err().to_string(),
+            "invalid fixed-point number: \"nope\"",
+        );
+        assert_eq!(fixed(0.5).to_string(), "0.5");
+
+        assert_eq!(fixed(2.75).to_i64(), Some(2));
+        assert_eq!(fixed(-2.75).to_i64(), Some(-2));
+        assert_eq!(fixed(-0.5).to_u64(), Some(0));
+        assert_eq!(fixed(-1.).to_u64(), None);
+    }
+
+    #[test]
+    fn test_fixed_from_str_radix() {
+        assert_eq!(Fixed::from_str_radix("-1.8", 16).unwrap(), fixed(-1.5));
+        assert_eq!(Fixed::from_str_radix("10.1", 2).unwrap(), fixed(2.5));
+        assert_eq!(Fixed::from_str_radix("+ff", 16).unwrap(), fixed(255.));
+        assert_eq!(Fixed::from_str_radix(".4", 8).unwrap(), fixed(0.5));
+        assert_eq!(Fixed::from_str_radix("7fff", 16).unwrap(), fixed(32767.));
+
+        for &(value, radix) in &[("8000", 16), ("1", 37), ("", 16), ("-.", 16), ("12", 2)] {
+            assert!(Fixed::from_str_radix(value, radix).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_fixed_arithmetic() {
+        assert_eq!(fixed(1.5) + fixed(2.25), fixed(3.75));
+        assert_eq!(fixed(1.5) - fixed(2.25), fixed(-0.75));
+        assert_eq!(fixed(1.5) * fixed(-2.5), fixed(-3.75));
+        assert_eq!(fixed(7.5) / fixed(2.5), fixed(3.));
+        assert_eq!(fixed(7.5) % fixed(2.), fixed(1.5));
+        assert_eq!(-fixed(7.5), fixed(-7.5));
+        assert_eq!(fixed(-7.5).abs(), fixed(7.5));
+
+        let mut value = fixed(1.);
+        value += fixed(2.);
+        value *= fixed(0.5);
+        value -= fixed(0.25);
+        value /= fixed(0.5);
+        assert_eq!(value, fixed(2.5));
+
+        assert_eq!(fixed(2.25).sqrt(), Some(fixed(1.5)));
+        assert_eq!(fixed(2.).sqrt(), Some(Fixed::from_bits(92681)));
+        assert_eq!(Fixed::ZERO.sqrt(), Some(Fixed::ZERO));
+        assert_eq!(fixed(-1.).sqrt(), None);
+
+        assert_eq!(Fixed::MAX.checked_add(Fixed::EPSILON), None);
+        assert_eq!(Fixed::MIN.checked_sub(Fixed::EPSILON), None);
+        assert_eq!(fixed(256.).checked_mul(fixed(128.)), None);
+        assert_eq!(fixed(256.).checked_div(fixed(0.0078125)), None);
+        assert_eq!(fixed(1.).checked_div(Fixed::ZERO), None);
+        assert_eq!(fixed(1.).checked_rem(Fixed::ZERO), None);
+        assert_eq!(Fixed::MIN.checked_neg(), None);
+        assert_eq!(fixed(1.5).checked_mul(fixed(2.)), Some(fixed(3.)));
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed-point multiplication overflowed")]
+    fn test_fixed_mul_overflow() {
+        let _ = fixed(256.) * fixed(128.);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixed-point remainder by zero")]
+    fn test_fixed_rem_zero() {
+        let _ = fixed(1.) % Fixed::ZERO;
+    }
+
+    #[test]
+    fn test_fixed_wrap() {
+        let max = Fixed::from_int(10);
+
+        assert_eq!(wrap_inc(Fixed::from_int(9), max), Fixed::ZERO);
+        assert_eq!(wrap_dec(Fixed::ZERO, max), Fixed::from_int(9));
+        assert_eq!(wrap_inc_by(fixed(9.5), max, fixed(1.25)), fixed(0.75));
+        assert_eq!(wrap_inc_by(fixed(0.5), max, fixed(-1.25)), fixed(9.25));
+    }
+
+    #[test]
+    fn test_fixed_distance() {
+        let v1 = to_fixed_vector(Vector2::new(1., 2.));
+        let v2 = to_fixed_vector(Vector2::new(4., -2.));
+
+        assert_eq!(fast_distance_fixed(v1, v2), fixed(5.5));
+        assert_eq!(
+            fast_distance_origin_fixed(v1 - v2),
+            fast_distance_fixed(v1, v2)
+        );
+        assert_eq!(to_float_vector(v1 + v2 * fixed(0.5)), Vector2::new(3., 1.));
+
+        let size = to_fixed_vector(Vector2::new(10., 20.));
+        let from = to_fixed_vector(Vector2::new(1., 1.));
+        let to = to_fixed_vector(Vector2::new(9., 19.));
+        assert_eq!(
+            wrapped_displacement(from, to, size),
+            to_fixed_vector(Vector2::new(-2., -2.)),
+        );
+        assert_eq!(wrapped_fast_distance(from, to, size), Fixed::from_int(3));
+    }
+
+    #[test]
+    fn test_fixed_is_deterministic() {
+        // Accumulating a velocity gives exactly the same result as the integer computation.
+        let velocity = Fixed::from_bits(12345);
+        let position = (0..1000).fold(Fixed::ZERO, |position, _| position + velocity);
+        assert_eq!(position.to_bits(), 12345 * 1000);
+    }
+}
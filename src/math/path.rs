@@ -0,0 +1,492 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Parametric motion paths
+//!
+//! Curves are parameterized over `[0, 1]`. Moving along a curve at a constant rate of its
+//! parameter does not generally move at a constant speed; `ArcLengthCurve` reparameterizes a curve
+//! by distance for that purpose.
+
+use std::cmp::Ordering;
+
+use cgmath::{InnerSpace, Vector2};
+
+/// A parametric curve.
+pub trait Curve {
+    /// The point on the curve at a parameter within `[0, 1]`.
+    fn point(&self, t: f32) -> Vector2<f32>;
+
+    /// The tangent of the curve at a parameter within `[0, 1]`.
+    ///
+    /// This is the derivative of the curve and is not normalized.
+    fn tangent(&self, t: f32) -> Vector2<f32>;
+}
+
+#[inline]
+// `f32::clamp` is not available on Rust 1.47 and keeps NaN, which this treats as zero.
+#[allow(clippy::manual_clamp)]
+fn clamp_t(t: f32) -> f32 {
+    t.max(0.).min(1.)
+}
+
+/// Find the segment of a piecewise curve which contains a parameter.
+///
+/// Returns the index of the segment and the parameter within it.
+fn segment(t: f32, count: usize) -> (usize, f32) {
+    let scaled = clamp_t(t) * (count as f32);
+    let index = (scaled as usize).min(count - 1);
+    (index, scaled - (index as f32))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A quadratic Bézier curve.
+pub struct QuadraticBezier {
+    /// The start of the curve.
+    pub p0: Vector2<f32>,
+    /// The control point.
+    pub p1: Vector2<f32>,
+    /// The end of the curve.
+    pub p2: Vector2<f32>,
+}
+
+impl Curve for QuadraticBezier {
+    fn point(&self, t: f32) -> Vector2<f32> {
+        let t = clamp_t(t);
+        let u = 1. - t;
+
+        self.p0 * (u * u) + self.p1 * (2. * u * t) + self.p2 * (t * t)
+    }
+
+    fn tangent(&self, t: f32) -> Vector2<f32> {
+        let t = clamp_t(t);
+
+        (self.p1 - self.p0) * (2. * (1. - t)) + (self.p2 - self.p1) * (2. * t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A cubic Bézier curve.
+pub struct CubicBezier {
+    /// The start of the curve.
+    pub p0: Vector2<f32>,
+    /// The first control point.
+    pub p1: Vector2<f32>,
+    /// The second control point.
+    pub p2: Vector2<f32>,
+    /// The end of the curve.
+    pub p3: Vector2<f32>,
+}
+
+impl Curve for CubicBezier {
+    fn point(&self, t: f32) -> Vector2<f32> {
+        let t = clamp_t(t);
+        let u = 1. - t;
+
+        self.p0 * (u * u * u)
+            + self.p1 * (3. * u * u * t)
+            + self.p2 * (3. * u * t * t)
+            + self.p3 * (t * t * t)
+    }
+
+    fn tangent(&self, t: f32) -> Vector2<f32> {
+        let t = clamp_t(t);
+        let u = 1. - t;
+
+        (self.p1 - self.p0) * (3. * u * u)
+            + (self.p2 - self.p1) * (6. * u * t)
+            + (self.p3 - self.p2) * (3. * t * t)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A uniform Catmull-Rom spline.
+///
+/// The spline passes through each of its points. Each span between two points takes an equal
+/// share of the parameter range.
+pub struct CatmullRom {
+    points: Vec<Vector2<f32>>,
+}
+
+impl CatmullRom {
+    /// Create a spline through a set of points.
+    ///
+    /// At least two points are required.
+    pub fn new(points: Vec<Vector2<f32>>) -> Self {
+        assert!(
+            points.len() >= 2,
+            "a Catmull-Rom spline requires at least two points",
+        );
+
+        CatmullRom {
+            points,
+        }
+    }
+
+    /// The control points for a span of the spline.
+    ///
+    /// The ends are extended by repeating the first and last points.
+    fn span(&self, index: usize) -> [Vector2<f32>; 4] {
+        let last = self.points.len() - 1;
+
+        [
+            self.points[index.saturating_sub(1)],
+            self.points[index],
+            self.points[index + 1],
+            self.points[(index + 2).min(last)],
+        ]
+    }
+}
+
+impl Curve for CatmullRom {
+    fn point(&self, t: f32) -> Vector2<f32> {
+        let (index, t) = segment(t, self.points.len() - 1);
+        let [p0, p1, p2, p3] = self.span(index);
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        (p1 * 2.
+            + (p2 - p0) * t
+            + (p0 * 2. - p1 * 5. + p2 * 4. - p3) * t2
+            + (p1 * 3. - p0 - p2 * 3. + p3) * t3)
+            * 0.5
+    }
+
+    fn tangent(&self, t: f32) -> Vector2<f32> {
+        let count = self.points.len() - 1;
+        let (index, t) = segment(t, count);
+        let [p0, p1, p2, p3] = self.span(index);
+        let t2 = t * t;
+
+        // Scale by the number of spans to account for the span's share of the parameter.
+        ((p2 - p0)
+            + (p0 * 2. - p1 * 5. + p2 * 4. - p3) * (2. * t)
+            + (p1 * 3. - p0 - p2 * 3. + p3) * (3. * t2))
+            * (0.5 * count as f32)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A sequence of straight line segments.
+///
+/// Each segment takes an equal share of the parameter range.
+pub struct Polyline {
+    points: Vec<Vector2<f32>>,
+}
+
+impl Polyline {
+    /// Create a polyline through a set of points.
+    ///
+    /// At least two points are required.
+    pub fn new(points: Vec<Vector2<f32>>) -> Self {
+        assert!(points.len() >= 2, "a polyline requires at least two points");
+
+        Polyline {
+            points,
+        }
+    }
+}
+
+impl Curve for Polyline {
+    fn point(&self, t: f32) -> Vector2<f32> {
+        let (index, t) = segment(t, self.points.len() - 1);
+        let start = self.points[index];
+
+        start + (self.points[index + 1] - start) * t
+    }
+
+    fn tangent(&self, t: f32) -> Vector2<f32> {
+        let count = self.points.len() - 1;
+        let (index, _) = segment(t, count);
+
+        (self.points[index + 1] - self.points[index]) * (count as f32)
+    }
+}
+
+/// A curve which is sampled by distance along it.
+///
+/// The curve is approximated by a number of straight samples to build a table mapping distance to
+/// the curve's parameter. More samples give a more accurate mapping.
+#[derive(Debug, Clone)]
+pub struct ArcLengthCurve<C> {
+    curve: C,
+    /// The distance along the curve at evenly spaced parameters.
+    lengths: Vec<f32>,
+}
+
+impl<C> ArcLengthCurve<C>
+where
+    C: Curve,
+{
+    /// Reparameterize a curve by distance using a number of samples.
+    pub fn new(curve: C, samples: usize) -> Self {
+        let samples = samples.max(1);
+        let mut prev = curve.point(0.);
+        let mut length = 0.;
+
+        let lengths = (0..=samples)
+            .map(|i| {
+                let point = curve.point((i as f32) / (samples as f32));
+                length += (point - prev).magnitude();
+                prev = point;
+                length
+            })
+            .collect();
+
+        ArcLengthCurve {
+            curve,
+            lengths,
+        }
+    }
+
+    #[inline]
+    /// The underlying curve.
+    pub fn curve(&self) -> &C {
+        &self.curve
+    }
+
+    #[inline]
+    /// The total length of the curve.
+    pub fn length(&self) -> f32 {
+        *self
+            .lengths
+            .last()
+            .expect("there is always at least one sample")
+    }
+
+    /// The curve parameter at a distance along the curve.
+    ///
+    /// The distance is clamped to the length of the curve. A NaN distance is treated as zero.
+    pub fn parameter(&self, distance: f32) -> f32 {
+        let samples = self.lengths.len() - 1;
+        let distance = distance.max(0.).min(self.length());
+
+        // Find the first sample at or beyond the distance. Curves with non-finite points have NaN
+        // lengths from that point on; ordering them last keeps the table sorted.
+        let index = match self.lengths.binary_search_by(|length| {
+            length.partial_cmp(&distance).unwrap_or_else(|| {
+                if length.is_nan() {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            })
+        }) {
+            Ok(index) | Err(index) => index,
+        };
+
+        if index == 0 {
+            return 0.;
+        }
+
+        let before = self.lengths[index - 1];
+        let after = self.lengths[index];
+        let fraction = if after > before {
+            (distance - before) / (after - before)
+        } else {
+            0.
+        };
+
+        ((index - 1) as f32 + fraction) / (samples as f32)
+    }
+
+    /// The point at a distance along the curve.
+    pub fn point_at(&self, distance: f32) -> Vector2<f32> {
+        self.curve.point(self.parameter(distance))
+    }
+
+    /// The unit tangent at a distance along the curve.
+    ///
+    /// This may be used to orient objects moving along the curve. Returns a zero vector where the
+    /// curve has no direction.
+    pub fn direction_at(&self, distance: f32) -> Vector2<f32> {
+        let tangent = self.curve.tangent(self.parameter(distance));
+        let magnitude = tangent.magnitude();
+
+        if magnitude > 0. {
+            tangent / magnitude
+        } else {
+            tangent
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::math::path::{
+        ArcLengthCurve, CatmullRom, CubicBezier, Curve, Polyline, QuadraticBezier,
+    };
+
+    fn v(x: f32, y: f32) -> Vector2<f32> {
+        Vector2::new(x, y)
+    }
+
+    fn assert_near(actual: Vector2<f32>, expected: Vector2<f32>, epsilon: f32) {
+        assert!(
+            (actual - expected).magnitude() < epsilon,
+            "{:?} != {:?}",
+            actual,
+            expected,
+        );
+    }
+
+    /// Compare the tangent of a curve to a numerical derivative.
+    fn check_tangent<C>(curve: &C)
+    where
+        C: Curve,
+    {
+        let h = 1e-3;
+        (1..100).map(|i| (i as f32) / 100.).for_each(|t| {
+            let numeric = (curve.point(t + h) - curve.point(t - h)) / (2. * h);
+            let tangent = curve.tangent(t);
+            assert!(
+                (numeric - tangent).magnitude() < 0.01 * tangent.magnitude().max(1.),
+                "t = {}: {:?} != {:?}",
+                t,
+                numeric,
+                tangent,
+            );
+        });
+    }
+
+    #[test]
+    fn test_quadratic_bezier() {
+        let curve = QuadraticBezier {
+            p0: v(0., 0.),
+            p1: v(1., 2.),
+            p2: v(2., 0.),
+        };
+
+        assert_near(curve.point(0.), v(0., 0.), 1e-6);
+        assert_near(curve.point(0.5), v(1., 1.), 1e-6);
+        assert_near(curve.point(1.), v(2., 0.), 1e-6);
+        assert_near(curve.point(2.), v(2., 0.), 1e-6);
+        assert_near(curve.tangent(0.), v(2., 4.), 1e-6);
+        check_tangent(&curve);
+    }
+
+    #[test]
+    fn test_cubic_bezier() {
+        let curve = CubicBezier {
+            p0: v(0., 0.),
+            p1: v(0., 1.),
+            p2: v(1., 1.),
+            p3: v(1., 0.),
+        };
+
+        assert_near(curve.point(0.), v(0., 0.), 1e-6);
+        assert_near(curve.point(0.5), v(0.5, 0.75), 1e-6);
+        assert_near(curve.point(1.), v(1., 0.), 1e-6);
+        assert_near(curve.tangent(0.), v(0., 3.), 1e-6);
+        assert_near(curve.tangent(1.), v(0., -3.), 1e-6);
+        check_tangent(&curve);
+    }
+
+    #[test]
+    fn test_catmull_rom() {
+        let points = vec![v(0., 0.), v(1., 1.), v(2., 0.), v(3., 1.)];
+        let curve = CatmullRom::new(points.clone());
+
+        // The spline passes through each point.
+        points.iter().enumerate().for_each(|(i, &point)| {
+            assert_near(curve.point((i as f32) / 3.), point, 1e-5);
+        });
+        check_tangent(&curve);
+
+        // Two points is a straight line.
+        let line = CatmullRom::new(vec![v(0., 0.), v(2., 0.)]);
+        assert_near(line.point(0.5), v(1., 0.), 1e-6);
+    }
+
+    #[test]
+    fn test_polyline() {
+        let curve = Polyline::new(vec![v(0., 0.), v(2., 0.), v(2., 4.)]);
+
+        assert_near(curve.point(0.), v(0., 0.), 1e-6);
+        assert_near(curve.point(0.25), v(1., 0.), 1e-6);
+        assert_near(curve.point(0.5), v(2., 0.), 1e-6);
+        assert_near(curve.point(0.75), v(2., 2.), 1e-6);
+        assert_near(curve.point(1.), v(2., 4.), 1e-6);
+        assert_near(curve.tangent(0.1), v(4., 0.), 1e-6);
+        assert_near(curve.tangent(0.9), v(0., 8.), 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "requires at least two points")]
+    fn test_polyline_too_short() {
+        Polyline::new(vec![v(0., 0.)]);
+    }
+
+    #[test]
+    fn test_arc_length_polyline() {
+        // The segments have different lengths, so the parameter is not proportional to distance.
+        let curve = ArcLengthCurve::new(Polyline::new(vec![v(0., 0.), v(2., 0.), v(2., 4.)]), 64);
+
+        assert!((curve.length() - 6.).abs() < 1e-4);
+        assert_near(curve.point_at(0.), v(0., 0.), 1e-4);
+        assert_near(curve.point_at(1.), v(1., 0.), 1e-4);
+        assert_near(curve.point_at(3.), v(2., 1.), 1e-4);
+        assert_near(curve.point_at(6.), v(2., 4.), 1e-4);
+        assert_near(curve.point_at(10.), v(2., 4.), 1e-4);
+        assert_near(curve.point_at(-1.), v(0., 0.), 1e-4);
+        assert_near(curve.direction_at(1.), v(1., 0.), 1e-4);
+        assert_near(curve.direction_at(4.), v(0., 1.), 1e-4);
+    }
+
+    #[test]
+    fn test_arc_length_constant_speed() {
+        let curve = ArcLengthCurve::new(
+            CubicBezier {
+                p0: v(0., 0.),
+                p1: v(0., 10.),
+                p2: v(1., 10.),
+                p3: v(10., 0.),
+            },
+            256,
+        );
+
+        let steps = 50;
+        let step = curve.length() / (steps as f32);
+        let points = (0..=steps)
+            .map(|i| curve.point_at((i as f32) * step))
+            .collect::<Vec<_>>();
+
+        points.windows(2).for_each(|pair| {
+            let distance = (pair[1] - pair[0]).magnitude();
+            assert!(
+                (distance - step).abs() < 0.01 * step,
+                "{} != {}",
+                distance,
+                step
+            );
+        });
+    }
+
+    #[test]
+    fn test_arc_length_non_finite() {
+        let curve = ArcLengthCurve::new(
+            Polyline::new(vec![v(0., 0.), v(2., 0.), v(f32::NAN, 4.)]),
+            64,
+        );
+
+        assert!(curve.length().is_nan());
+        assert_near(curve.point_at(1.), v(1., 0.), 1e-4);
+        assert_eq!(curve.parameter(f32::NAN), 0.);
+        [3., 10., f32::INFINITY].iter().for_each(|&distance| {
+            let t = curve.parameter(distance);
+            assert!((0. ..=1.).contains(&t), "{}", t);
+        });
+
+        let curve = ArcLengthCurve::new(
+            Polyline::new(vec![v(0., 0.), v(f32::INFINITY, 0.), v(2., 4.)]),
+            64,
+        );
+        [-1., 1., 1e30, f32::INFINITY, f32::NAN]
+            .iter()
+            .for_each(|&distance| {
+                let t = curve.parameter(distance);
+                assert!((0. ..=1.).contains(&t), "{}", t);
+            });
+    }
+}
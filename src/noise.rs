@@ -0,0 +1,370 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+use std::f32::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+use cgmath::{Vector2, Vector3};
+
+use crate::rand::{Rand, RandBackend};
+
+/// The number of lattice points before the noise repeats.
+const PERIOD: usize = 256;
+
+/// Gradients for 2D noise: unit vectors at 45 degree increments.
+const GRADIENTS_2D: [(f32, f32); 8] = [
+    (1., 0.),
+    (-1., 0.),
+    (0., 1.),
+    (0., -1.),
+    (FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    (FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    (-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// The scale of 3D noise: `sqrt(2 / 3)`.
+///
+/// The 3D gradients have a length of `sqrt(2)`, so the raw result is within
+/// `[-sqrt(6) / 2, sqrt(6) / 2]`.
+const NOISE3_SCALE: f32 = 0.816_496_6;
+
+/// Gradients for 3D noise: the midpoints of the edges of a cube.
+const GRADIENTS_3D: [(f32, f32, f32); 12] = [
+    (1., 1., 0.),
+    (-1., 1., 0.),
+    (1., -1., 0.),
+    (-1., -1., 0.),
+    (1., 0., 1.),
+    (-1., 0., 1.),
+    (1., 0., -1.),
+    (-1., 0., -1.),
+    (0., 1., 1.),
+    (0., -1., 1.),
+    (0., 1., -1.),
+    (0., -1., -1.),
+];
+
+#[inline]
+/// The smoothing curve for interpolating between lattice points.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+#[inline]
+/// Linearly interpolate between two values.
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+#[inline]
+/// Split a coordinate into its lattice cell and the offset within the cell.
+fn cell(x: f32) -> (usize, f32) {
+    let floor = x.floor();
+    ((floor as i64).rem_euclid(PERIOD as i64) as usize, x - floor)
+}
+
+/// Coherent gradient noise.
+///
+/// The noise is generated from a permutation table drawn from a random number source, so a noise
+/// generator created from a source with the same seed always produces the same values. Results
+/// are approximately within `[-1, 1]` and are zero at integer coordinates.
+#[derive(Clone)]
+pub struct Noise {
+    /// The permutation table, repeated twice to avoid wrapping indices.
+    perm: [u8; PERIOD * 2],
+}
+
+impl Noise {
+    /// Create a new noise generator from a random number source.
+    pub fn new<B>(rand: &mut Rand<B>) -> Self
+    where
+        B: RandBackend,
+    {
+        let mut table = [0u8; PERIOD];
+        table
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, entry)| *entry = i as u8);
+        rand.shuffle(&mut table);
+
+        let mut perm = [0u8; PERIOD * 2];
+        perm[..PERIOD].copy_from_slice(&table);
+        perm[PERIOD..].copy_from_slice(&table);
+
+        Noise {
+            perm,
+        }
+    }
+
+    #[inline]
+    fn hash(&self, i: usize) -> usize {
+        self.perm[i] as usize
+    }
+
+    /// Compute 1D noise at a point.
+    pub fn noise1(&self, x: f32) -> f32 {
+        let (xi, xf) = cell(x);
+
+        let grad = |hash: usize, x: f32| (hash as f32 / 127.5 - 1.) * x;
+
+        let u = fade(xf);
+
+        // The gradients are within `[-1, 1]`, so the raw result is within `[-0.5, 0.5]`.
+        2. * lerp(u, grad(self.hash(xi), xf), grad(self.hash(xi + 1), xf - 1.))
+    }
+
+    /// Compute 2D noise at a point.
+    pub fn noise2(&self, p: Vector2<f32>) -> f32 {
+        let (xi, xf) = cell(p.x);
+        let (yi, yf) = cell(p.y);
+
+        let grad = |hash: usize, x: f32, y: f32| {
+            let (gx, gy) = GRADIENTS_2D[hash & 7];
+            gx * x + gy * y
+        };
+
+        let aa = self.hash(self.hash(xi) + yi);
+        let ab = self.hash(self.hash(xi) + yi + 1);
+        let ba = self.hash(self.hash(xi + 1) + yi);
+        let bb = self.hash(self.hash(xi + 1) + yi + 1);
+
+        let u = fade(xf);
+        let v = fade(yf);
+
+        // With unit gradients, the raw result is within `[-sqrt(2) / 2, sqrt(2) / 2]`.
+        SQRT_2
+            * lerp(
+                v,
+                lerp(u, grad(aa, xf, yf), grad(ba, xf - 1., yf)),
+                lerp(u, grad(ab, xf, yf - 1.), grad(bb, xf - 1., yf - 1.)),
+            )
+    }
+
+    /// Compute 3D noise at a point.
+    pub fn noise3(&self, p: Vector3<f32>) -> f32 {
+        let (xi, xf) = cell(p.x);
+        let (yi, yf) = cell(p.y);
+        let (zi, zf) = cell(p.z);
+
+        let grad = |hash: usize, x: f32, y: f32, z: f32| {
+            let (gx, gy, gz) = GRADIENTS_3D[hash % 12];
+            gx * x + gy * y + gz * z
+        };
+
+        let a = self.hash(xi) + yi;
+        let aa = self.hash(a) + zi;
+        let ab = self.hash(a + 1) + zi;
+        let b = self.hash(xi + 1) + yi;
+        let ba = self.hash(b) + zi;
+        let bb = self.hash(b + 1) + zi;
+
+        let u = fade(xf);
+        let v = fade(yf);
+        let w = fade(zf);
+
+        NOISE3_SCALE
+            * lerp(
+                w,
+                lerp(
+                    v,
+                    lerp(
+                        u,
+                        grad(self.hash(aa), xf, yf, zf),
+                        grad(self.hash(ba), xf - 1., yf, zf),
+                    ),
+                    lerp(
+                        u,
+                        grad(self.hash(ab), xf, yf - 1., zf),
+                        grad(self.hash(bb), xf - 1., yf - 1., zf),
+                    ),
+                ),
+                lerp(
+                    v,
+                    lerp(
+                        u,
+                        grad(self.hash(aa + 1), xf, yf, zf - 1.),
+                        grad(self.hash(ba + 1), xf - 1., yf, zf - 1.),
+                    ),
+                    lerp(
+                        u,
+                        grad(self.hash(ab + 1), xf, yf - 1., zf - 1.),
+                        grad(self.hash(bb + 1), xf - 1., yf - 1., zf - 1.),
+                    ),
+                ),
+            )
+    }
+
+    /// Sum octaves of noise at increasing frequencies.
+    ///
+    /// Each octave is sampled at `lacunarity` times the frequency of the previous octave and
+    /// contributes `persistence` times its amplitude. The result is normalized by the total
+    /// amplitude so that it stays within the range of a single octave.
+    fn fractal<F>(octaves: u32, lacunarity: f32, persistence: f32, sample: F) -> f32
+    where
+        F: Fn(f32) -> f32,
+    {
+        let (sum, total, _, _) =
+            (0..octaves).fold((0., 0., 1., 1.), |(sum, total, frequency, amplitude), _| {
+                (
+                    sum + sample(frequency) * amplitude,
+                    total + amplitude,
+                    frequency * lacunarity,
+                    amplitude * persistence,
+                )
+            });
+
+        if total > 0. {
+            sum / total
+        } else {
+            0.
+        }
+    }
+
+    /// Compute fractal 1D noise at a point.
+    pub fn fractal1(&self, x: f32, octaves: u32, lacunarity: f32, persistence: f32) -> f32 {
+        Self::fractal(octaves, lacunarity, persistence, |frequency| {
+            self.noise1(x * frequency)
+        })
+    }
+
+    /// Compute fractal 2D noise at a point.
+    pub fn fractal2(
+        &self,
+        p: Vector2<f32>,
+        octaves: u32,
+        lacunarity: f32,
+        persistence: f32,
+    ) -> f32 {
+        Self::fractal(octaves, lacunarity, persistence, |frequency| {
+            self.noise2(p * frequency)
+        })
+    }
+
+    /// Compute fractal 3D noise at a point.
+    pub fn fractal3(
+        &self,
+        p: Vector3<f32>,
+        octaves: u32,
+        lacunarity: f32,
+        persistence: f32,
+    ) -> f32 {
+        Self::fractal(octaves, lacunarity, persistence, |frequency| {
+            self.noise3(p * frequency)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{Vector2, Vector3};
+
+    use crate::noise::Noise;
+    use crate::rand::Rand;
+
+    fn noise(seed: u32) -> Noise {
+        let mut rand: Rand = Rand::with_seed(seed);
+        Noise::new(&mut rand)
+    }
+
+    fn samples() -> impl Iterator<Item = f32> {
+        (-200..200).map(|i| (i as f32) * 0.173)
+    }
+
+    #[test]
+    fn test_noise_is_deterministic() {
+        let noise_0 = noise(1);
+        let noise_1 = noise(1);
+        let noise_2 = noise(2);
+
+        samples().for_each(|x| {
+            let p2 = Vector2::new(x, x * 0.7);
+            let p3 = Vector3::new(x, x * 0.7, -x * 0.3);
+
+            assert_eq!(noise_0.noise1(x), noise_1.noise1(x));
+            assert_eq!(noise_0.noise2(p2), noise_1.noise2(p2));
+            assert_eq!(noise_0.noise3(p3), noise_1.noise3(p3));
+        });
+
+        assert!(samples().any(|x| noise_0.noise1(x) != noise_2.noise1(x)));
+        assert!(samples().any(|x| {
+            let p = Vector2::new(x, x * 0.7);
+            noise_0.noise2(p) != noise_2.noise2(p)
+        }));
+        assert!(samples().any(|x| {
+            let p = Vector3::new(x, x * 0.7, -x * 0.3);
+            noise_0.noise3(p) != noise_2.noise3(p)
+        }));
+    }
+
+    #[test]
+    fn test_noise_is_zero_on_lattice() {
+        let noise = noise(1);
+
+        (-10..10).map(|i| i as f32).for_each(|i| {
+            assert_eq!(noise.noise1(i), 0.);
+            assert_eq!(noise.noise2(Vector2::new(i, -i)), 0.);
+            assert_eq!(noise.noise3(Vector3::new(i, -i, 2. * i)), 0.);
+        });
+    }
+
+    #[test]
+    fn test_noise_is_bounded() {
+        let noise = noise(1);
+
+        samples().for_each(|x| {
+            samples().step_by(7).for_each(|y| {
+                let n1 = noise.noise1(x + y);
+                let n2 = noise.noise2(Vector2::new(x, y));
+                let n3 = noise.noise3(Vector3::new(x, y, x - y));
+                let f2 = noise.fractal2(Vector2::new(x, y), 4, 2., 0.5);
+
+                assert!((-1. ..=1.).contains(&n1), "noise1: {}", n1);
+                assert!((-1. ..=1.).contains(&n2), "noise2: {}", n2);
+                assert!((-1. ..=1.).contains(&n3), "noise3: {}", n3);
+                assert!((-1. ..=1.).contains(&f2), "fractal2: {}", f2);
+            })
+        });
+    }
+
+    #[test]
+    fn test_noise_is_continuous() {
+        let noise = noise(1);
+        let step = 0.001;
+
+        samples().for_each(|x| {
+            let p2 = Vector2::new(x, 0.5 * x);
+            let p3 = Vector3::new(x, 0.5 * x, 0.25 * x);
+            let d2 = Vector2::new(step, step);
+            let d3 = Vector3::new(step, step, step);
+
+            assert!((noise.noise1(x) - noise.noise1(x + step)).abs() < 0.01);
+            assert!((noise.noise2(p2) - noise.noise2(p2 + d2)).abs() < 0.01);
+            assert!((noise.noise3(p3) - noise.noise3(p3 + d3)).abs() < 0.01);
+        });
+    }
+
+    #[test]
+    fn test_noise_repeats() {
+        let noise = noise(1);
+
+        samples().for_each(|x| {
+            assert!((noise.noise1(x) - noise.noise1(x + 256.)).abs() < 1e-3);
+        });
+    }
+
+    #[test]
+    fn test_fractal() {
+        let noise = noise(1);
+
+        samples().for_each(|x| {
+            let p2 = Vector2::new(x, 0.5 * x);
+            let p3 = Vector3::new(x, 0.5 * x, 0.25 * x);
+
+            // A single octave is the plain noise.
+            assert_eq!(noise.fractal1(x, 1, 2., 0.5), noise.noise1(x));
+            assert_eq!(noise.fractal2(p2, 1, 2., 0.5), noise.noise2(p2));
+            assert_eq!(noise.fractal3(p3, 1, 2., 0.5), noise.noise3(p3));
+            assert_eq!(noise.fractal1(x, 0, 2., 0.5), 0.);
+        });
+    }
+}
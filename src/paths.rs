@@ -3,74 +3,499 @@
 
 use std::borrow::Borrow;
 use std::env;
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
+use thiserror::Error;
+
+mod migrate;
+
+pub use self::migrate::*;
+
+/// Errors which may occur when determining paths.
+#[derive(Debug, Error)]
+pub enum PathsError {
+    /// The running executable could not be determined.
+    #[error("could not determine the running executable: {}", _0)]
+    CurrentExe(#[source] io::Error),
+    /// The executable path has no file name.
+    #[error("the executable path has no file name: {}", _0.display())]
+    NoExecutableName(PathBuf),
+    /// The executable path has no parent directory.
+    #[error("the executable path has no parent directory: {}", _0.display())]
+    NoParentDirectory(PathBuf),
+    /// The project directories for the application could not be determined.
+    #[error("failed to determine the project directories for {}", _0)]
+    ProjectDirs(String),
+}
+
+/// A convenience alias for path results.
+pub type PathsResult<T> = Result<T, PathsError>;
+
+/// The file next to the executable which enables portable mode.
+pub const PORTABLE_MARKER: &str = "portable.txt";
+/// The environment variable which overrides the configuration directory.
+pub const CONFIG_DIR_ENV_VAR: &str = "ABAGAMES_CONFIG_DIR";
+/// The environment variable which overrides the data directory.
+pub const DATA_DIR_ENV_VAR: &str = "ABAGAMES_DATA_DIR";
+
+/// The well-known directories for a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathsDir {
+    /// Configuration files.
+    Config,
+    /// Data files.
+    Data,
+    /// Generated files which may be deleted at any time.
+    Cache,
+    /// Recorded replays.
+    Replays,
+    /// High score tables.
+    Scores,
+    /// Screenshots.
+    Screenshots,
+    /// Log files.
+    Logs,
+    /// User overrides of game assets.
+    Assets,
+}
 
 /// Paths for configuration and data storage.
+///
+/// Directories are not created until they are needed; see `Paths::create_dir`.
 pub struct Paths {
     /// Directory for storing configuration files.
     pub config_dir: PathBuf,
     /// Directory for storing data files.
     pub data_dir: PathBuf,
+    /// Directory for storing generated files.
+    pub cache_dir: PathBuf,
+    /// Directory for storing replays.
+    pub replays_dir: PathBuf,
+    /// Directory for storing high scores.
+    pub scores_dir: PathBuf,
+    /// Directory for storing screenshots.
+    pub screenshots_dir: PathBuf,
+    /// Directory for storing logs.
+    pub logs_dir: PathBuf,
+    /// Directory searched for assets which override those shipped with the game.
+    pub assets_dir: PathBuf,
+    /// Directory holding the data shipped with the game.
+    ///
+    /// This is within the install tree rather than the user's directories and may be read-only.
+    pub shipped_data_dir: PathBuf,
+    /// Files moved out of the swapped directories used by older versions on the first run.
+    pub migration: Option<PathsMigration>,
 }
 
 impl Paths {
     /// Construct paths based on a given source tree.
     ///
     /// This allows a binary to be run in both an install tree and a build tree.
+    ///
+    /// Panics if the paths cannot be determined; see `Paths::try_new`.
     pub fn new<P: AsRef<Path>>(source_path: P) -> Self {
-        let (base_dir, is_install) = Self::base_path_dir(source_path.as_ref());
+        Self::try_new(source_path).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        if is_install {
-            Self::from_install()
+    /// Construct paths based on a given source tree.
+    ///
+    /// This allows a binary to be run in both an install tree and a build tree.
+    ///
+    /// If a `portable.txt` file is next to the executable, all files are kept next to the
+    /// executable instead. The `ABAGAMES_CONFIG_DIR` and `ABAGAMES_DATA_DIR` environment variables
+    /// override the configuration and data directories, respectively; the other directories are
+    /// placed within the data directory. Data shipped with an installed game is expected in the
+    /// `share/<executable name>` directory of the install tree.
+    pub fn try_new<P: AsRef<Path>>(source_path: P) -> PathsResult<Self> {
+        let mut exe_path = env::current_exe().map_err(PathsError::CurrentExe)?;
+        exe_path.pop();
+
+        Self::discover(
+            source_path.as_ref(),
+            exe_path,
+            Self::env_dir(CONFIG_DIR_ENV_VAR),
+            Self::env_dir(DATA_DIR_ENV_VAR),
+        )
+    }
+
+    /// Determine the paths given the directory of the executable and any overrides.
+    fn discover(
+        source_path: &Path,
+        exe_dir: PathBuf,
+        config_override: Option<PathBuf>,
+        data_override: Option<PathBuf>,
+    ) -> PathsResult<Self> {
+        let paths = if exe_dir.join(PORTABLE_MARKER).is_file() {
+            Self::from_build(exe_dir)
         } else {
-            Self::from_build(base_dir)
+            let (base_dir, is_install) = Self::base_path_dir(source_path, exe_dir)?;
+
+            if !is_install {
+                Self::from_build(base_dir)
+            } else if let (Some(_), Some(data_dir)) = (&config_override, &data_override) {
+                // The user's directories are not needed at all.
+                Paths {
+                    shipped_data_dir: Self::install_data_dir(&base_dir)?,
+                    ..Self::from_build(data_dir.clone())
+                }
+            } else if config_override.is_some() || data_override.is_some() {
+                // Overridden directories must not be touched by migration.
+                Self::from_project_dirs(&base_dir)?
+            } else {
+                Self::from_install(&base_dir)?
+            }
+        };
+
+        Ok(paths.with_overrides(config_override, data_override))
+    }
+
+    /// A directory given by an environment variable.
+    fn env_dir(name: &str) -> Option<PathBuf> {
+        env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Replace the configuration and data directories.
+    fn with_overrides(self, config_dir: Option<PathBuf>, data_dir: Option<PathBuf>) -> Self {
+        let paths = if let Some(data_dir) = data_dir {
+            Paths {
+                config_dir: self.config_dir,
+                shipped_data_dir: self.shipped_data_dir,
+                migration: self.migration,
+                ..Self::from_build(data_dir)
+            }
+        } else {
+            self
+        };
+
+        if let Some(config_dir) = config_dir {
+            Paths {
+                config_dir,
+                ..paths
+            }
+        } else {
+            paths
         }
     }
 
+    /// The path to a well-known directory.
+    pub fn dir(&self, dir: PathsDir) -> &Path {
+        match dir {
+            PathsDir::Config => &self.config_dir,
+            PathsDir::Data => &self.data_dir,
+            PathsDir::Cache => &self.cache_dir,
+            PathsDir::Replays => &self.replays_dir,
+            PathsDir::Scores => &self.scores_dir,
+            PathsDir::Screenshots => &self.screenshots_dir,
+            PathsDir::Logs => &self.logs_dir,
+            PathsDir::Assets => &self.assets_dir,
+        }
+    }
+
+    /// The path to a well-known directory, creating it if necessary.
+    ///
+    /// Files should only be written into directories created this way. When the configuration or
+    /// data directory of an install tree is created, it is marked as using the corrected layout so
+    /// that its files are not migrated later.
+    pub fn create_dir(&self, dir: PathsDir) -> io::Result<&Path> {
+        let path = self.dir(dir);
+        let unmarked = if self.migration.is_some() {
+            vec![&self.config_dir, &self.data_dir]
+                .into_iter()
+                .filter(|dir| path.starts_with(dir) && !dir.exists())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        fs::create_dir_all(path)?;
+        unmarked.into_iter().try_for_each(|dir| mark_dir(dir))?;
+
+        Ok(path)
+    }
+
     /// Paths based on the build directory.
+    ///
+    /// This is also used for portable installs; everything is kept within the directory.
     fn from_build(path: PathBuf) -> Self {
         Paths {
             config_dir: path.clone(),
-            data_dir: path,
+            data_dir: path.clone(),
+            cache_dir: path.join("cache"),
+            replays_dir: path.join("replays"),
+            scores_dir: path.join("scores"),
+            screenshots_dir: path.join("screenshots"),
+            logs_dir: path.join("logs"),
+            assets_dir: path.join("assets"),
+            shipped_data_dir: path.clone(),
+            migration: None,
         }
     }
 
     /// Paths based on the install directory.
-    fn from_install() -> Self {
-        let exe_path = env::current_exe().expect("could not determine the running executable");
+    ///
+    /// Files left in the swapped directories used by older versions are moved on the first run.
+    fn from_install(install_dir: &Path) -> PathsResult<Self> {
+        let paths = Self::from_project_dirs(install_dir)?;
+        let migration = migrate_swapped_dirs(
+            &paths.config_dir,
+            &paths.data_dir,
+            &[
+                &paths.cache_dir,
+                &paths.replays_dir,
+                &paths.scores_dir,
+                &paths.screenshots_dir,
+                &paths.logs_dir,
+                &paths.assets_dir,
+            ],
+        );
+
+        Ok(Paths {
+            migration,
+            ..paths
+        })
+    }
+
+    /// Paths based on the user's directories for the executable.
+    fn from_project_dirs(install_dir: &Path) -> PathsResult<Self> {
+        let appname = Self::app_name()?;
+
+        let project_dirs = ProjectDirs::from("", "", appname.borrow())
+            .ok_or_else(|| PathsError::ProjectDirs(appname.clone()))?;
+
+        let config_dir = project_dirs.config_dir().to_path_buf();
+        let data_dir = project_dirs.data_local_dir().join("data");
+        let scores_dir = data_dir.join("scores");
+
+        let local_dir = project_dirs.data_local_dir();
+        Ok(Paths {
+            config_dir,
+            data_dir,
+            cache_dir: project_dirs.cache_dir().to_path_buf(),
+            replays_dir: local_dir.join("replays"),
+            scores_dir,
+            screenshots_dir: local_dir.join("screenshots"),
+            logs_dir: local_dir.join("logs"),
+            assets_dir: local_dir.join("assets"),
+            shipped_data_dir: install_dir.join("share").join(appname),
+            migration: None,
+        })
+    }
+
+    /// The name of the application, taken from the executable.
+    fn app_name() -> PathsResult<String> {
+        let exe_path = env::current_exe().map_err(PathsError::CurrentExe)?;
         let appname_osstr = exe_path
             .file_name()
-            .expect("there should be a filename on the executable");
-        let appname = appname_osstr.to_string_lossy();
+            .ok_or_else(|| PathsError::NoExecutableName(exe_path.clone()))?;
 
-        let project_dirs = ProjectDirs::from("", "", appname.borrow())
-            .expect("failed to create project directories");
+        Ok(appname_osstr.to_string_lossy().into_owned())
+    }
 
-        Paths {
-            config_dir: project_dirs.data_local_dir().join("data"),
-            data_dir: project_dirs.config_dir().to_path_buf(),
-        }
+    /// The directory holding the data shipped with an installed game.
+    fn install_data_dir(install_dir: &Path) -> PathsResult<PathBuf> {
+        Ok(install_dir.join("share").join(Self::app_name()?))
     }
 
     /// Return the base path for the installation.
-    fn base_path_dir(source_path: &Path) -> (PathBuf, bool) {
-        let mut exe_path = env::current_exe().expect("could not determine the running executable");
-
-        exe_path.pop(); // build config (build) or bin (install)
+    ///
+    /// The executable directory is the build config (build) or bin (install) directory.
+    fn base_path_dir(source_path: &Path, mut exe_path: PathBuf) -> PathsResult<(PathBuf, bool)> {
+        let parent_name = exe_path
+            .file_name()
+            .ok_or_else(|| PathsError::NoParentDirectory(exe_path.clone()))?;
 
-        if "bin"
-            == exe_path
-                .file_name()
-                .expect("the executable path should have a parent directory")
-        {
+        if "bin" == parent_name {
             // In an install tree.
             exe_path.pop(); // install root
 
-            (exe_path, true)
+            Ok((exe_path, true))
         } else {
-            (source_path.to_path_buf(), false)
+            Ok((source_path.to_path_buf(), false))
         }
     }
 }
+
+#[cfg(test)]
+impl Paths {
+    /// Paths for the test executable, ignoring overrides in the environment.
+    pub(crate) fn for_test<P: AsRef<Path>>(source_path: P) -> Self {
+        Self::discover(source_path.as_ref(), test_exe_dir(), None, None).unwrap()
+    }
+}
+
+#[cfg(test)]
+/// The directory containing the test executable.
+fn test_exe_dir() -> PathBuf {
+    let mut exe_dir = env::current_exe().unwrap();
+    exe_dir.pop();
+    exe_dir
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use crate::paths::{
+        migrate_swapped_dirs, test_exe_dir, Paths, PathsDir, PathsError, PathsMigration,
+        PORTABLE_MARKER,
+    };
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_paths_build_tree() {
+        // Test executables live in the build tree.
+        let source = Path::new("source");
+        let paths = Paths::discover(source, test_exe_dir(), None, None).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("source"));
+        assert_eq!(paths.data_dir, PathBuf::from("source"));
+        assert_eq!(paths.cache_dir, PathBuf::from("source/cache"));
+        assert_eq!(paths.replays_dir, PathBuf::from("source/replays"));
+        assert_eq!(paths.scores_dir, PathBuf::from("source/scores"));
+        assert_eq!(paths.screenshots_dir, PathBuf::from("source/screenshots"));
+        assert_eq!(paths.logs_dir, PathBuf::from("source/logs"));
+        assert_eq!(paths.assets_dir, PathBuf::from("source/assets"));
+        assert_eq!(paths.shipped_data_dir, PathBuf::from("source"));
+        assert!(paths.migration.is_none());
+    }
+
+    #[test]
+    fn test_paths_create_dir() {
+        let temp = TempDir::new("paths");
+        let root = temp.path().join("root");
+        let paths = Paths::for_test(&root);
+
+        assert_eq!(paths.dir(PathsDir::Replays), root.join("replays"));
+        assert!(!root.exists());
+
+        let replays = paths.create_dir(PathsDir::Replays).unwrap();
+        assert_eq!(replays, root.join("replays"));
+        assert!(replays.is_dir());
+        assert!(!paths.dir(PathsDir::Logs).exists());
+        // Creating an existing directory is fine.
+        paths.create_dir(PathsDir::Replays).unwrap();
+        // Build trees are not marked.
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_paths_create_dir_marks_layout() {
+        let temp = TempDir::new("paths-marks");
+        let root = temp.path();
+        let paths = Paths {
+            config_dir: root.join("config"),
+            data_dir: root.join("local").join("data"),
+            migration: Some(PathsMigration::default()),
+            ..Paths::from_build(root.join("local"))
+        };
+
+        paths.create_dir(PathsDir::Scores).unwrap();
+        paths.create_dir(PathsDir::Config).unwrap();
+        fs::write(paths.config_dir.join("settings.txt"), "config").unwrap();
+        fs::create_dir_all(&paths.data_dir).unwrap();
+        fs::write(paths.data_dir.join("scores.txt"), "data").unwrap();
+
+        // Files in a directory created for the corrected layout are never migrated.
+        assert!(migrate_swapped_dirs(&paths.config_dir, &paths.data_dir, &[]).is_none());
+        assert!(paths.config_dir.join("settings.txt").exists());
+        assert!(paths.data_dir.join("scores.txt").exists());
+    }
+
+    #[test]
+    fn test_paths_portable() {
+        let exe_dir = TempDir::new("paths-portable");
+        let source = PathBuf::from("source");
+
+        let paths = Paths::discover(&source, exe_dir.path().to_path_buf(), None, None).unwrap();
+        assert_eq!(paths.config_dir, source);
+
+        fs::write(exe_dir.path().join(PORTABLE_MARKER), "").unwrap();
+        let paths = Paths::discover(&source, exe_dir.path().to_path_buf(), None, None).unwrap();
+        assert_eq!(paths.config_dir, exe_dir.path());
+        assert_eq!(paths.data_dir, exe_dir.path());
+        assert_eq!(paths.replays_dir, exe_dir.path().join("replays"));
+        assert!(paths.migration.is_none());
+
+        // Overrides take precedence over portable mode.
+        let config = PathBuf::from("config");
+        let paths = Paths::discover(
+            &source,
+            exe_dir.path().to_path_buf(),
+            Some(config.clone()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(paths.config_dir, config);
+        assert_eq!(paths.data_dir, exe_dir.path());
+    }
+
+    #[test]
+    fn test_paths_overrides() {
+        let source = PathBuf::from("source");
+        let config = PathBuf::from("config");
+        let data = PathBuf::from("data");
+        // An install tree; overriding both directories avoids looking up the user's directories.
+        let exe_dir = PathBuf::from("root").join("bin");
+        let exe_name = env::current_exe().unwrap().file_name().unwrap().to_owned();
+        let shipped = PathBuf::from("root").join("share").join(exe_name);
+
+        let paths =
+            Paths::discover(&source, exe_dir, Some(config.clone()), Some(data.clone())).unwrap();
+        assert_eq!(paths.config_dir, config);
+        assert_eq!(paths.data_dir, data);
+        assert_eq!(paths.shipped_data_dir, shipped);
+        assert_eq!(paths.cache_dir, data.join("cache"));
+        assert_eq!(paths.scores_dir, data.join("scores"));
+        assert_eq!(paths.logs_dir, data.join("logs"));
+        assert!(paths.migration.is_none());
+
+        let exe_dir = PathBuf::from("target").join("debug");
+        let paths = Paths::discover(&source, exe_dir.clone(), None, Some(data.clone())).unwrap();
+        assert_eq!(paths.config_dir, source);
+        assert_eq!(paths.data_dir, data);
+        assert_eq!(paths.screenshots_dir, data.join("screenshots"));
+        // The shipped data is not overridden.
+        assert_eq!(paths.shipped_data_dir, source);
+
+        let paths = Paths::discover(&source, exe_dir, Some(config.clone()), None).unwrap();
+        assert_eq!(paths.config_dir, config);
+        assert_eq!(paths.data_dir, source);
+        assert_eq!(paths.cache_dir, source.join("cache"));
+
+        // Overriding either directory in an install tree skips migrating the user's directories.
+        let exe_dir = PathBuf::from("root").join("bin");
+        let paths = Paths::discover(&source, exe_dir.clone(), Some(config.clone()), None).unwrap();
+        assert_eq!(paths.config_dir, config);
+        assert!(paths.migration.is_none());
+
+        let paths = Paths::discover(&source, exe_dir, None, Some(data.clone())).unwrap();
+        assert_eq!(paths.data_dir, data);
+        assert_eq!(paths.shipped_data_dir, shipped);
+        assert!(paths.migration.is_none());
+    }
+
+    #[test]
+    fn test_paths_error_messages() {
+        let err = PathsError::CurrentExe(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!(
+            err.to_string(),
+            "could not determine the running executable: gone",
+        );
+
+        let err = PathsError::NoExecutableName(PathBuf::from("/"));
+        assert_eq!(err.to_string(), "the executable path has no file name: /");
+
+        let err = PathsError::ProjectDirs("game".into());
+        assert_eq!(
+            err.to_string(),
+            "failed to determine the project directories for game",
+        );
+    }
+}
@@ -0,0 +1,421 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Migration of files from swapped install directories
+//!
+//! Install trees used to store configuration files in the data directory and data files in the
+//! configuration directory. The first run with the corrected layout swaps the contents of the two
+//! directories and leaves a marker behind in each of them so that this only happens once, even if
+//! the configuration directory is later deleted. Directories which do not exist yet are marked
+//! when `Paths::create_dir` creates them.
+
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The file marking that a directory uses the corrected layout.
+const MARKER_FILE: &str = ".abagames-paths-v2";
+/// The directory used to stage files while swapping.
+const STAGING_DIR: &str = ".abagames-paths-migration";
+
+/// A report of files moved out of swapped install directories.
+#[derive(Debug, Default)]
+pub struct PathsMigration {
+    /// Files which were moved, from their old location to their new one.
+    pub moved: Vec<(PathBuf, PathBuf)>,
+    /// Files which could not be moved and the reason why.
+    pub failed: Vec<(PathBuf, io::Error)>,
+}
+
+impl PathsMigration {
+    #[inline]
+    /// Whether anything was attempted.
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty() && self.failed.is_empty()
+    }
+}
+
+impl Display for PathsMigration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.moved.iter().try_for_each(|(from, to)| {
+            writeln!(f, "moved {} to {}", from.display(), to.display())
+        })?;
+        self.failed
+            .iter()
+            .try_for_each(|(path, err)| writeln!(f, "failed to move {}: {}", path.display(), err))
+    }
+}
+
+/// The entries in a directory; a missing directory has no entries.
+fn dir_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    match fs::read_dir(dir) {
+        Ok(entries) => {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect()
+        },
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Move a file into a directory, refusing to overwrite anything.
+fn move_into(path: &Path, dir: &Path) -> io::Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let target = dir.join(name);
+
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+
+    fs::create_dir_all(dir)?;
+    fs::rename(path, &target)?;
+
+    Ok(target)
+}
+
+/// Move each file into a directory, recording the renames made.
+///
+/// Stops at the first file which cannot be moved.
+fn move_all_into(
+    paths: &[PathBuf],
+    dir: &Path,
+    renames: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), (PathBuf, io::Error)> {
+    paths.iter().try_for_each(|path| {
+        let target = move_into(path, dir).map_err(|err| (path.clone(), err))?;
+        renames.push((path.clone(), target));
+        Ok(())
+    })
+}
+
+/// Undo renames, most recent first.
+fn undo_renames(renames: Vec<(PathBuf, PathBuf)>, report: &mut PathsMigration) {
+    renames.into_iter().rev().for_each(|(from, to)| {
+        let parent = from.parent().unwrap_or_else(|| Path::new(""));
+        if let Err(err) = fs::create_dir_all(parent).and_then(|_| fs::rename(&to, &from)) {
+            report.failed.push((to, err));
+        }
+    });
+}
+
+/// Mark a directory as using the corrected layout.
+pub(crate) fn mark_dir(dir: &Path) -> io::Result<()> {
+    fs::write(dir.join(MARKER_FILE), "")
+}
+
+/// Write the marker into each existing directory, removing any written markers on failure.
+///
+/// Missing directories are not created; they are marked when they are created through
+/// `Paths::create_dir`.
+fn write_markers(dirs: &[&Path]) -> Result<(), (PathBuf, io::Error)> {
+    let mut written = Vec::new();
+    let result = dirs.iter().filter(|dir| dir.is_dir()).try_for_each(|dir| {
+        mark_dir(dir).map_err(|err| (dir.join(MARKER_FILE), err))?;
+        written.push(dir.join(MARKER_FILE));
+        Ok(())
+    });
+
+    if result.is_err() {
+        written.iter().for_each(|marker| {
+            let _ = fs::remove_file(marker);
+        });
+    }
+
+    result
+}
+
+/// Swap the contents of the configuration and data directories if it has not been done yet.
+///
+/// If any file cannot be moved, the files which were moved are put back and the directories are
+/// left unmigrated so that the next run tries again. Returns `None` if either directory has
+/// already been migrated; the marker in the data directory still guards against migrating again
+/// if the configuration directory is deleted.
+///
+/// The `keep` directories are never moved. These are the other directories of `Paths` which may
+/// be inside of the configuration or data directories.
+pub(crate) fn migrate_swapped_dirs(
+    config_dir: &Path,
+    data_dir: &Path,
+    keep: &[&Path],
+) -> Option<PathsMigration> {
+    let marker = config_dir.join(MARKER_FILE);
+    if marker.exists() || data_dir.join(MARKER_FILE).exists() {
+        return None;
+    }
+
+    let mut report = PathsMigration::default();
+    let staging = config_dir.join(STAGING_DIR);
+
+    let list = |dir: &Path| dir_entries(dir).map_err(|err| (dir.to_path_buf(), err));
+    let (old_data, old_config) =
+        match list(config_dir).and_then(|old_data| Ok((old_data, list(data_dir)?))) {
+            Ok(lists) => lists,
+            Err(failure) => {
+                report.failed.push(failure);
+                return Some(report);
+            },
+        };
+    // The old data files are in the configuration directory. On some platforms the data
+    // directory and the other directories are inside of the configuration directory; they must
+    // stay where they are.
+    let is_kept = |path: &PathBuf| keep.iter().any(|dir| dir.starts_with(path));
+    let old_data = old_data
+        .into_iter()
+        .filter(|path| {
+            !data_dir.starts_with(path) && !is_kept(path) && *path != marker && *path != staging
+        })
+        .collect::<Vec<_>>();
+    let old_config = old_config
+        .into_iter()
+        .filter(|path| !is_kept(path))
+        .collect::<Vec<_>>();
+
+    // Stage the old data files so that names used in both directories do not collide.
+    let mut renames = Vec::new();
+    let result = move_all_into(&old_data, &staging, &mut renames)
+        .and_then(|_| move_all_into(&old_config, config_dir, &mut renames))
+        .and_then(|_| {
+            let staged = renames[..old_data.len()]
+                .iter()
+                .map(|(_, staged)| staged.clone())
+                .collect::<Vec<_>>();
+            move_all_into(&staged, data_dir, &mut renames)
+        })
+        .and_then(|_| {
+            if staging.exists() {
+                fs::remove_dir(&staging).map_err(|err| (staging.clone(), err))?;
+            }
+            write_markers(&[config_dir, data_dir])
+        });
+
+    if let Err(failure) = result {
+        report.failed.push(failure);
+        undo_renames(renames, &mut report);
+        // Only remove the staging directory if everything made it out of it.
+        if staging.exists() {
+            if let Err(err) = fs::remove_dir(&staging) {
+                report.failed.push((staging, err));
+            }
+        }
+    } else {
+        let (staged, rest) = renames.split_at(old_data.len());
+        let (config_moves, data_moves) = rest.split_at(old_config.len());
+        report.moved.extend(config_moves.iter().cloned());
+        report.moved.extend(
+            staged
+                .iter()
+                .zip(data_moves)
+                .map(|((path, _), (_, target))| (path.clone(), target.clone())),
+        );
+    }
+
+    Some(report)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::paths::migrate::{migrate_swapped_dirs, MARKER_FILE, STAGING_DIR};
+    use crate::test_util::TempDir;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_separate_dirs() {
+        let root = TempDir::new("migrate-separate");
+        let config_dir = root.path().join("config");
+        let data_dir = root.path().join("data");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        // Old layout: configuration in the data directory and vice versa.
+        fs::write(data_dir.join("settings.txt"), "config").unwrap();
+        fs::write(data_dir.join("shared"), "old config").unwrap();
+        fs::write(config_dir.join("scores.txt"), "data").unwrap();
+        fs::write(config_dir.join("shared"), "old data").unwrap();
+        fs::create_dir_all(config_dir.join("replays")).unwrap();
+        fs::write(config_dir.join("replays").join("1"), "replay").unwrap();
+
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert!(report.failed.is_empty(), "{}", report);
+        assert_eq!(report.moved.len(), 5);
+        assert!(report.moved.contains(&(
+            data_dir.join("settings.txt"),
+            config_dir.join("settings.txt")
+        )));
+
+        assert_eq!(read(&config_dir.join("settings.txt")), "config");
+        assert_eq!(read(&config_dir.join("shared")), "old config");
+        assert_eq!(read(&data_dir.join("scores.txt")), "data");
+        assert_eq!(read(&data_dir.join("shared")), "old data");
+        assert_eq!(read(&data_dir.join("replays").join("1")), "replay");
+        assert!(!config_dir.join("scores.txt").exists());
+
+        // Running again does nothing.
+        fs::write(config_dir.join("new.txt"), "config").unwrap();
+        assert!(migrate_swapped_dirs(&config_dir, &data_dir, &[]).is_none());
+        assert!(config_dir.join("new.txt").exists());
+    }
+
+    #[test]
+    fn test_migrate_nested_dirs() {
+        let root = TempDir::new("migrate-nested");
+        let config_dir = root.path().to_path_buf();
+        let data_dir = root.path().join("data");
+        fs::create_dir_all(&data_dir).unwrap();
+
+        fs::write(data_dir.join("settings.txt"), "config").unwrap();
+        fs::write(config_dir.join("scores.txt"), "data").unwrap();
+
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert!(report.failed.is_empty(), "{}", report);
+        assert_eq!(report.moved.len(), 2);
+
+        assert_eq!(read(&config_dir.join("settings.txt")), "config");
+        assert_eq!(read(&data_dir.join("scores.txt")), "data");
+        assert!(!data_dir.join("data").exists());
+    }
+
+    #[test]
+    fn test_migrate_failed_move() {
+        let root = TempDir::new("migrate-failed");
+        let config_dir = root.path().join("config");
+        let data_dir = root.path().join("data");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        fs::write(data_dir.join("settings.txt"), "config").unwrap();
+        fs::write(config_dir.join("scores.txt"), "data").unwrap();
+        // This collides with the staging directory, so it cannot be moved.
+        fs::write(data_dir.join(STAGING_DIR), "blocker").unwrap();
+
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert_eq!(report.failed.len(), 1, "{}", report);
+        assert_eq!(report.failed[0].0, data_dir.join(STAGING_DIR));
+        assert!(report.moved.is_empty(), "{}", report);
+
+        // Everything is put back and no marker is left behind.
+        assert_eq!(read(&data_dir.join("settings.txt")), "config");
+        assert_eq!(read(&config_dir.join("scores.txt")), "data");
+        assert_eq!(read(&data_dir.join(STAGING_DIR)), "blocker");
+        assert!(!config_dir.join(STAGING_DIR).exists());
+        assert!(!config_dir.join(MARKER_FILE).exists());
+        assert!(!data_dir.join(MARKER_FILE).exists());
+        assert!(!config_dir.join("settings.txt").exists());
+
+        // The next run tries again.
+        fs::remove_file(data_dir.join(STAGING_DIR)).unwrap();
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert!(report.failed.is_empty(), "{}", report);
+        assert_eq!(report.moved.len(), 2);
+        assert_eq!(read(&config_dir.join("settings.txt")), "config");
+        assert_eq!(read(&data_dir.join("scores.txt")), "data");
+        assert!(config_dir.join(MARKER_FILE).exists());
+        assert!(data_dir.join(MARKER_FILE).exists());
+    }
+
+    #[test]
+    fn test_migrate_deleted_config_dir() {
+        let root = TempDir::new("migrate-deleted-config");
+        let config_dir = root.path().join("config");
+        let data_dir = root.path().join("data");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+
+        fs::write(data_dir.join("settings.txt"), "config").unwrap();
+        fs::write(config_dir.join("scores.txt"), "data").unwrap();
+
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert!(report.failed.is_empty(), "{}", report);
+
+        // Resetting the settings leaves the data directory alone.
+        fs::remove_dir_all(&config_dir).unwrap();
+        assert!(migrate_swapped_dirs(&config_dir, &data_dir, &[]).is_none());
+        assert_eq!(read(&data_dir.join("scores.txt")), "data");
+        assert!(!config_dir.exists());
+
+        // Settings written again are not moved later.
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("settings.txt"), "config").unwrap();
+        assert!(migrate_swapped_dirs(&config_dir, &data_dir, &[]).is_none());
+        assert_eq!(read(&config_dir.join("settings.txt")), "config");
+        assert_eq!(read(&data_dir.join("scores.txt")), "data");
+    }
+
+    #[test]
+    fn test_migrate_missing_config_dir() {
+        let root = TempDir::new("migrate-missing-config");
+        let config_dir = root.path().join("config");
+        let data_dir = root.path().join("data");
+        fs::create_dir_all(&data_dir).unwrap();
+
+        // Only the old configuration directory was ever used.
+        fs::write(data_dir.join("settings.txt"), "config").unwrap();
+
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert!(report.failed.is_empty(), "{}", report);
+        assert_eq!(
+            report.moved,
+            vec![(data_dir.join("settings.txt"), config_dir.join("settings.txt"))],
+        );
+        assert_eq!(read(&config_dir.join("settings.txt")), "config");
+        assert!(!data_dir.join("settings.txt").exists());
+        assert!(config_dir.join(MARKER_FILE).exists());
+        assert!(data_dir.join(MARKER_FILE).exists());
+        assert!(migrate_swapped_dirs(&config_dir, &data_dir, &[]).is_none());
+    }
+
+    #[test]
+    fn test_migrate_keeps_other_dirs() {
+        // On some platforms, the other directories share a parent with the data directory.
+        let root = TempDir::new("migrate-keep");
+        let config_dir = root.path().to_path_buf();
+        let data_dir = root.path().join("data");
+        let scores_dir = root.path().join("scores");
+        let logs_dir = data_dir.join("logs");
+        fs::create_dir_all(&scores_dir).unwrap();
+        fs::create_dir_all(&logs_dir).unwrap();
+
+        fs::write(data_dir.join("settings.txt"), "config").unwrap();
+        fs::write(config_dir.join("state.txt"), "data").unwrap();
+        fs::write(scores_dir.join("normal.txt"), "scores").unwrap();
+        fs::write(logs_dir.join("game.log"), "log").unwrap();
+
+        let report =
+            migrate_swapped_dirs(&config_dir, &data_dir, &[&scores_dir, &logs_dir]).unwrap();
+        assert!(report.failed.is_empty(), "{}", report);
+        assert_eq!(report.moved.len(), 2, "{}", report);
+
+        assert_eq!(read(&config_dir.join("settings.txt")), "config");
+        assert_eq!(read(&data_dir.join("state.txt")), "data");
+        assert_eq!(read(&scores_dir.join("normal.txt")), "scores");
+        assert_eq!(read(&logs_dir.join("game.log")), "log");
+        assert!(!data_dir.join("scores").exists());
+        assert!(!config_dir.join("logs").exists());
+    }
+
+    #[test]
+    fn test_migrate_fresh_install() {
+        let root = TempDir::new("migrate-fresh");
+        let config_dir = root.path().join("config");
+        let data_dir = root.path().join("data");
+
+        let report = migrate_swapped_dirs(&config_dir, &data_dir, &[]).unwrap();
+        assert!(report.is_empty(), "{}", report);
+        assert_eq!(report.to_string(), "");
+        // Nothing is created until it is needed.
+        assert!(!config_dir.exists());
+        assert!(!data_dir.exists());
+    }
+}
@@ -1,40 +1,267 @@
 // Distributed under the OSI-approved BSD 2-Clause License.
 // See accompanying LICENSE file for details.
 
-use chrono::Utc;
-use rand_core::SeedableRng;
-use rand_mt::Mt19937GenRand32;
+use std::convert::TryFrom;
+use std::env;
+use std::num::ParseIntError;
+use std::panic::Location;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand_core::{RngCore, SeedableRng};
+
+pub use rand_mt::Mt19937GenRand32;
+pub use rand_pcg::Pcg32;
+pub use rand_xoshiro::Xoshiro128PlusPlus;
+
+mod trace;
+
+pub use self::trace::*;
+
+/// A source of random bits which may be used by `Rand`.
+pub trait RandBackend: RngCore {
+    /// Create a new source from a 32-bit seed.
+    fn from_seed_u32(seed: u32) -> Self;
+}
+
+impl RandBackend for Mt19937GenRand32 {
+    fn from_seed_u32(seed: u32) -> Self {
+        Mt19937GenRand32::new(seed)
+    }
+}
+
+impl RandBackend for Pcg32 {
+    fn from_seed_u32(seed: u32) -> Self {
+        Pcg32::seed_from_u64(u64::from(seed))
+    }
+}
+
+impl RandBackend for Xoshiro128PlusPlus {
+    fn from_seed_u32(seed: u32) -> Self {
+        Xoshiro128PlusPlus::seed_from_u64(u64::from(seed))
+    }
+}
+
+/// The environment variable which may be used to override generated seeds.
+pub const SEED_ENV_VAR: &str = "ABAGAMES_SEED";
+
+/// The seed used by default-constructed sources (the default seed of MT19937).
+const DEFAULT_SEED: u32 = 5489;
+
+/// The number of seeds which have been generated by this process.
+static SEED_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// The number of seeds which have been derived from the environment by this process.
+static ENV_SEED_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Mix the bits of a value into a seed.
+///
+/// This is the SplitMix64 finalizer, so nearby values produce unrelated seeds.
+fn mix_seed(value: u64) -> u32 {
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+
+    (z ^ (z >> 32)) as u32
+}
+
+/// Parse the value of the `ABAGAMES_SEED` environment variable.
+fn seed_from_env_value(value: &str) -> Result<u32, ParseIntError> {
+    value.trim().parse()
+}
+
+/// The seed of the `index`th source created from a base seed.
+///
+/// The first source uses the base seed itself so that a seed reported by a single source may be
+/// used to reproduce it.
+fn derive_seed(base: u32, index: u64) -> u32 {
+    if index == 0 {
+        base
+    } else {
+        mix_seed((u64::from(base) << 32) ^ index.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Where the seed of a random number source came from.
+pub enum SeedSource {
+    /// The default seed was used.
+    Default,
+    /// The seed was generated from the current time.
+    Generated,
+    /// The seed was read from the `ABAGAMES_SEED` environment variable.
+    Environment,
+    /// The `ABAGAMES_SEED` environment variable is not a valid seed, so the seed was generated
+    /// from the current time instead.
+    InvalidEnvironment,
+    /// The seed was given explicitly.
+    Explicit,
+}
 
 /// Seedable and repeatable source of random numbers.
-#[derive(Default)]
-pub struct Rand {
-    twister: Mt19937GenRand32,
+///
+/// The Mersenne Twister is used by default to match the original games. Other backends are faster
+/// and may be used for randomness which does not need to match.
+pub struct Rand<B = Mt19937GenRand32> {
+    backend: B,
+    seed: u32,
+    seed_source: SeedSource,
+    trace: Option<RandTrace>,
 }
 
 impl Rand {
     /// Create a new random number source.
+    ///
+    /// See `Rand::with_backend` for how the seed is chosen.
     pub fn new() -> Self {
-        let seed = Utc::now().timestamp() as u32;
+        Self::with_backend()
+    }
+}
+
+impl Default for Rand {
+    fn default() -> Self {
+        Self::with_default_seed()
+    }
+}
+
+impl<B> Rand<B>
+where
+    B: RandBackend,
+{
+    /// Create a new random number source using a specific backend.
+    ///
+    /// If the `ABAGAMES_SEED` environment variable is set, the first source uses it as its seed and
+    /// later sources derive their seeds from it, so that a run is repeatable while sources still
+    /// produce different streams. Otherwise, the seed is generated from the current time and a
+    /// per-process counter so that sources created at the same time do not share a seed. The
+    /// chosen seed is available from `Rand::seed` so that it may be logged. An invalid seed in the
+    /// environment is reported by `Rand::seed_source`.
+    pub fn with_backend() -> Self {
+        let (seed, source) = Self::choose_seed(env::var(SEED_ENV_VAR).ok().as_deref());
+
+        Self::with_seed_source(seed, source)
+    }
+
+    /// Create a new random number source with the default seed.
+    ///
+    /// This is `Rand::default` for backends other than the Mersenne Twister.
+    pub fn with_default_seed() -> Self {
+        Self::with_seed_source(DEFAULT_SEED, SeedSource::Default)
+    }
 
+    /// Create a new random number source with a given seed.
+    pub fn with_seed(seed: u32) -> Self {
+        Self::with_seed_source(seed, SeedSource::Explicit)
+    }
+
+    fn with_seed_source(seed: u32, seed_source: SeedSource) -> Self {
         Rand {
-            twister: Mt19937GenRand32::from_seed(seed.to_be_bytes()),
+            backend: B::from_seed_u32(seed),
+            seed,
+            seed_source,
+            trace: None,
+        }
+    }
+
+    /// Choose a seed given the value of the `ABAGAMES_SEED` environment variable, if any.
+    fn choose_seed(env_value: Option<&str>) -> (u32, SeedSource) {
+        match env_value.map(seed_from_env_value) {
+            Some(Ok(base)) => {
+                let index = ENV_SEED_COUNTER.fetch_add(1, Ordering::Relaxed);
+                (derive_seed(base, index), SeedSource::Environment)
+            },
+            Some(Err(_)) => (Self::generate_seed(), SeedSource::InvalidEnvironment),
+            None => (Self::generate_seed(), SeedSource::Generated),
         }
     }
 
+    /// Generate a seed from the current time.
+    fn generate_seed() -> u32 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+        let count = SEED_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        mix_seed(nanos ^ count.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    #[inline]
+    /// The seed of the source.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    #[inline]
+    /// Where the seed of the source came from.
+    pub fn seed_source(&self) -> SeedSource {
+        self.seed_source
+    }
+
     #[inline]
     /// Set the seed of the source.
     pub fn set_seed(&mut self, seed: u32) {
-        self.twister.reseed(seed)
+        self.backend = B::from_seed_u32(seed);
+        self.seed = seed;
+        self.seed_source = SeedSource::Explicit;
+    }
+
+    /// Start recording draws from the source.
+    ///
+    /// At most `capacity` of the most recent draws are kept along with the location of the code
+    /// which requested them. Any previous trace is discarded. Tracing does not affect the values
+    /// which are drawn.
+    pub fn enable_trace(&mut self, capacity: usize) {
+        self.trace = Some(RandTrace::new(capacity));
+    }
+
+    /// Stop recording draws from the source and return the trace.
+    pub fn disable_trace(&mut self) -> Option<RandTrace> {
+        self.trace.take()
     }
 
     #[inline]
+    /// The trace of draws from the source, if enabled.
+    pub fn trace(&self) -> Option<&RandTrace> {
+        self.trace.as_ref()
+    }
+
+    #[inline]
+    #[track_caller]
     /// Get the next 32-bit unsigned integer.
     pub fn next_u32(&mut self) -> u32 {
-        self.twister.next_u32()
+        let value = self.backend.next_u32();
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.record(Location::caller(), value);
+        }
+
+        value
+    }
+
+    #[track_caller]
+    /// Get a 32-bit unsigned integer between 0 and `range` without modulo bias.
+    ///
+    /// Values from the low end of the generator's output which would bias the result are rejected
+    /// and redrawn.
+    fn next_bounded(&mut self, range: u32) -> u32 {
+        let threshold = range.wrapping_neg() % range;
+
+        loop {
+            let value = self.next_u32();
+            if value >= threshold {
+                break value % range;
+            }
+        }
     }
 
     #[inline]
+    #[track_caller]
     /// Get a 32-bit unsigned integer between 0 and `n`.
+    ///
+    /// Like the original games, this takes the next value modulo `n`, so sequences for a seed
+    /// match theirs. Smaller values are slightly more likely for large values of `n`; see
+    /// `next_int_unbiased`.
     pub fn next_int(&mut self, n: u32) -> u32 {
         if n == 0 {
             0
@@ -44,42 +271,157 @@ impl Rand {
     }
 
     #[inline]
+    #[track_caller]
+    /// Get a 32-bit unsigned integer between 0 and `n` without modulo bias.
+    ///
+    /// This may draw more than one value from the source, so sequences differ from `next_int`.
+    pub fn next_int_unbiased(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            0
+        } else {
+            self.next_bounded(n)
+        }
+    }
+
+    #[inline]
+    #[track_caller]
     /// Get a 32-bit signed integer in the range of `-n` to `n`.
+    ///
+    /// Values of `n` beyond `i32::MAX` are treated as `i32::MAX`.
     pub fn next_int_signed(&mut self, n: u32) -> i32 {
+        let n = n.min(i32::MAX as u32);
+
         if n == 0 {
             0
         } else {
-            ((self.next_u32() % (2 * n + 1)) as i32) - (n as i32)
+            (i64::from(self.next_u32() % (2 * n + 1)) - i64::from(n)) as i32
         }
     }
 
     #[inline]
+    #[track_caller]
     /// Get a real number between 0 and 1.
     fn next_real(&mut self) -> f32 {
         (f64::from(self.next_u32()) * (1. / 4_294_967_295.)) as f32
     }
 
     #[inline]
+    #[track_caller]
     /// Get a real number between 0 and `n`.
     pub fn next_float(&mut self, n: f32) -> f32 {
         self.next_real() * n
     }
 
     #[inline]
+    #[track_caller]
     /// Get a real number between `-n` and `n`.
     pub fn next_float_signed(&mut self, n: f32) -> f32 {
         self.next_real() * (2. * n) - n
     }
+
+    #[inline]
+    #[track_caller]
+    /// Get `true` with a probability of `p`.
+    pub fn next_bool(&mut self, p: f32) -> bool {
+        if p <= 0. {
+            false
+        } else if p >= 1. {
+            true
+        } else {
+            self.next_real() < p
+        }
+    }
+
+    #[track_caller]
+    /// Get a normally distributed real number with the given mean and standard deviation.
+    ///
+    /// Uses the Box-Muller transform; two values are drawn from the source for each result.
+    pub fn next_gaussian(&mut self, mean: f32, std_dev: f32) -> f32 {
+        // Keep `u1` within `(0, 1]` so that the logarithm is finite.
+        let u1 = 1. - f64::from(self.next_u32()) * (1. / 4_294_967_296.);
+        let u2 = f64::from(self.next_u32()) * (1. / 4_294_967_296.);
+
+        let z = (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos();
+
+        mean + std_dev * (z as f32)
+    }
+
+    #[track_caller]
+    /// Choose an item from a slice.
+    ///
+    /// Returns `None` if the slice is empty.
+    ///
+    /// Panics if the slice has more than `u32::MAX` items.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        let len = slice_len(items);
+
+        if len == 0 {
+            None
+        } else {
+            Some(&items[self.next_int_unbiased(len) as usize])
+        }
+    }
+
+    #[track_caller]
+    /// Choose an item from a table of items and their relative weights.
+    ///
+    /// Items with a weight of zero are never chosen. Returns `None` if the table is empty or all
+    /// weights are zero.
+    ///
+    /// Panics if the total weight of the table does not fit in a `u32`.
+    pub fn choose_weighted<'a, T>(&mut self, table: &'a [(T, u32)]) -> Option<&'a T> {
+        let total = table
+            .iter()
+            .try_fold(0u32, |total, &(_, weight)| total.checked_add(weight))
+            .expect("the total weight of the table overflows");
+
+        if total == 0 {
+            return None;
+        }
+
+        let mut target = self.next_int_unbiased(total);
+        table.iter().find_map(|&(ref item, weight)| {
+            if target < weight {
+                Some(item)
+            } else {
+                target -= weight;
+                None
+            }
+        })
+    }
+
+    #[track_caller]
+    /// Shuffle the items in a slice.
+    ///
+    /// Panics if the slice has more than `u32::MAX` items.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        // A closure would hide the location of the caller from traces.
+        for i in (1..slice_len(items)).rev() {
+            let j = self.next_int_unbiased(i + 1) as usize;
+            items.swap(i as usize, j);
+        }
+    }
+}
+
+/// The length of a slice which items are drawn from.
+///
+/// Panics if the slice is too long to draw an index with a single value.
+fn slice_len<T>(items: &[T]) -> u32 {
+    u32::try_from(items.len()).expect("slices with more than `u32::MAX` items are not supported")
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
     use std::fmt::Debug;
     use std::iter;
 
     use chrono::Utc;
 
-    use crate::rand::Rand;
+    use crate::rand::{
+        derive_seed, seed_from_env_value, Mt19937GenRand32, Pcg32, Rand, RandBackend, SeedSource,
+        Xoshiro128PlusPlus,
+    };
 
     fn run_rand<T, F>(closure: F) -> Vec<T>
     where
@@ -142,6 +484,17 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_next_int_signed_large() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        // The full range of `u32` does not fit into the result.
+        [i32::MAX as u32, i32::MAX as u32 + 1, u32::MAX]
+            .iter()
+            .for_each(|&n| assert!(verify_rand(|| rand.next_int_signed(n), |i| -i32::MAX <= i,)));
+    }
+
     #[test]
     fn test_seed_is_deterministic() {
         let mut rand_0 = Rand::new();
@@ -178,5 +531,292 @@ mod test {
             run_rand(|| rand_0.next_float_signed(-0.4)),
             run_rand(|| rand_1.next_float_signed(-0.4)),
         );
+        assert_eq!(
+            run_rand(|| rand_0.next_bool(0.3)),
+            run_rand(|| rand_1.next_bool(0.3)),
+        );
+        assert_eq!(
+            run_rand(|| rand_0.next_gaussian(1., 2.)),
+            run_rand(|| rand_1.next_gaussian(1., 2.)),
+        );
+        assert_eq!(
+            run_rand(|| rand_0.choose(&[1, 2, 3, 4])),
+            run_rand(|| rand_1.choose(&[1, 2, 3, 4])),
+        );
+        assert_eq!(
+            run_rand(|| rand_0.choose_weighted(&[(1, 3), (2, 0), (3, 5)])),
+            run_rand(|| rand_1.choose_weighted(&[(1, 3), (2, 0), (3, 5)])),
+        );
+        assert_eq!(
+            run_rand(|| {
+                let mut items = [1, 2, 3, 4, 5];
+                rand_0.shuffle(&mut items);
+                items
+            }),
+            run_rand(|| {
+                let mut items = [1, 2, 3, 4, 5];
+                rand_1.shuffle(&mut items);
+                items
+            }),
+        );
+    }
+
+    #[test]
+    fn test_next_int_matches_original() {
+        let mut rand = Rand::new();
+        let mut raw = Rand::new();
+        rand.set_seed(1);
+        raw.set_seed(1);
+
+        [1, 3, 10, 1000, (1 << 31) + (1 << 30), u32::MAX]
+            .iter()
+            .for_each(|&n| {
+                (0..100).for_each(|_| {
+                    assert_eq!(rand.next_int(n), raw.next_u32() % n);
+                    assert_eq!(
+                        i64::from(rand.next_int_signed(n)),
+                        i64::from(raw.next_u32() % (2 * n.min(i32::MAX as u32) + 1))
+                            - i64::from(n.min(i32::MAX as u32)),
+                    );
+                })
+            });
+    }
+
+    #[test]
+    fn test_next_int_unbiased() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        assert_eq!(rand.next_int_unbiased(0), 0);
+        assert!(verify_rand(|| rand.next_int_unbiased(10), |i| i < 10));
+
+        // With a range just above half of the generator's range, a plain modulo would produce
+        // values in the lower half twice as often as values in the upper half.
+        let n = (1 << 31) + (1 << 30);
+        let low = iter::repeat_with(|| rand.next_int_unbiased(n))
+            .take(10000)
+            .filter(|&i| i < n / 3)
+            .count();
+
+        assert!((2800..3900).contains(&low), "low count: {}", low);
+    }
+
+    #[test]
+    fn test_next_bool() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        assert!(verify_rand(|| rand.next_bool(0.), |b| !b));
+        assert!(verify_rand(|| rand.next_bool(1.), |b| b));
+        assert!(verify_rand(|| rand.next_bool(-1.), |b| !b));
+        assert!(verify_rand(|| rand.next_bool(2.), |b| b));
+    }
+
+    #[test]
+    fn test_next_gaussian() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        let samples = iter::repeat_with(|| rand.next_gaussian(5., 2.))
+            .take(10000)
+            .collect::<Vec<_>>();
+        let mean = samples.iter().sum::<f32>() / (samples.len() as f32);
+        let variance =
+            samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f32>() / (samples.len() as f32);
+
+        assert!((mean - 5.).abs() < 0.1, "mean: {}", mean);
+        assert!(
+            (variance.sqrt() - 2.).abs() < 0.1,
+            "std dev: {}",
+            variance.sqrt()
+        );
+    }
+
+    #[test]
+    fn test_choose() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        let empty: [i32; 0] = [];
+        assert_eq!(rand.choose(&empty), None);
+        assert_eq!(rand.choose(&[1]), Some(&1));
+        assert!(verify_rand(
+            || rand.choose(&[1, 2, 3]),
+            |i| [1, 2, 3].iter().any(|j| Some(j) == i),
+        ));
+    }
+
+    #[test]
+    fn test_choose_weighted() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        let empty: [(i32, u32); 0] = [];
+        assert_eq!(rand.choose_weighted(&empty), None);
+        assert_eq!(rand.choose_weighted(&[(1, 0), (2, 0)]), None);
+        assert!(verify_rand(
+            || rand.choose_weighted(&[(1, 0), (2, 1), (3, 0)]),
+            |i| i == Some(&2),
+        ));
+
+        let table = [(0, 1), (1, 3)];
+        let ones = iter::repeat_with(|| rand.choose_weighted(&table))
+            .take(10000)
+            .filter(|&i| i == Some(&1))
+            .count();
+        assert!((7000..8000).contains(&ones), "count: {}", ones);
+    }
+
+    #[test]
+    #[should_panic(expected = "the total weight of the table overflows")]
+    fn test_choose_weighted_overflow() {
+        let mut rand = Rand::new();
+        rand.choose_weighted(&[(1, u32::MAX), (2, 1)]);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "slices with more than `u32::MAX` items are not supported")]
+    fn test_choose_long_slice() {
+        let mut rand = Rand::new();
+        let items = vec![(); u32::MAX as usize + 1];
+        rand.choose(&items);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[should_panic(expected = "slices with more than `u32::MAX` items are not supported")]
+    fn test_shuffle_long_slice() {
+        let mut rand = Rand::new();
+        let mut items = vec![(); u32::MAX as usize + 1];
+        rand.shuffle(&mut items);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut rand = Rand::new();
+        rand.set_seed(1);
+
+        let mut empty: [i32; 0] = [];
+        rand.shuffle(&mut empty);
+
+        let mut items = (0..20).collect::<Vec<_>>();
+        rand.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_default_backend_is_mt() {
+        // The first output of the reference MT19937 implementation with its default seed.
+        let mut rand = Rand::new();
+        rand.set_seed(5489);
+        assert_eq!(rand.next_u32(), 3_499_211_612);
+
+        let mut rand = Rand::<Mt19937GenRand32>::with_seed(5489);
+        assert_eq!(rand.next_u32(), 3_499_211_612);
+    }
+
+    fn check_backend<B>()
+    where
+        B: RandBackend,
+    {
+        let mut rand_0 = Rand::<B>::with_backend();
+        let mut rand_1 = Rand::<B>::with_seed(2);
+
+        rand_0.set_seed(2);
+
+        assert_eq!(
+            run_rand(|| rand_0.next_u32()),
+            run_rand(|| rand_1.next_u32()),
+        );
+        assert!(verify_rand(|| rand_0.next_int(10), |i| i < 10));
+        assert!(verify_rand(
+            || rand_0.next_int_signed(10),
+            |i| (-10..=10).contains(&i),
+        ));
+        assert!(verify_rand(
+            || rand_0.next_float(2.),
+            |f| (0. ..=2.).contains(&f),
+        ));
+        assert!(verify_rand(
+            || rand_0.next_float_signed(2.),
+            |f| (-2. ..=2.).contains(&f),
+        ));
+    }
+
+    #[test]
+    fn test_backends() {
+        check_backend::<Mt19937GenRand32>();
+        check_backend::<Pcg32>();
+        check_backend::<Xoshiro128PlusPlus>();
+    }
+
+    #[test]
+    fn test_backends_differ() {
+        let mut mt = Rand::<Mt19937GenRand32>::with_seed(1);
+        let mut pcg = Rand::<Pcg32>::with_seed(1);
+        let mut xoshiro = Rand::<Xoshiro128PlusPlus>::with_seed(1);
+
+        let mt_values = run_rand(|| mt.next_u32());
+        let pcg_values = run_rand(|| pcg.next_u32());
+        let xoshiro_values = run_rand(|| xoshiro.next_u32());
+
+        assert_ne!(mt_values, pcg_values);
+        assert_ne!(mt_values, xoshiro_values);
+        assert_ne!(pcg_values, xoshiro_values);
+    }
+
+    #[test]
+    fn test_seed_provenance() {
+        // The backend is inferred without annotations.
+        let mut rand = Rand::default();
+        rand.next_int(3);
+        assert_eq!(rand.seed(), 5489);
+        assert_eq!(rand.seed_source(), SeedSource::Default);
+
+        let rand = Rand::<Pcg32>::with_default_seed();
+        assert_eq!(rand.seed(), 5489);
+        assert_eq!(rand.seed_source(), SeedSource::Default);
+
+        let mut rand = Rand::<Pcg32>::with_seed(10);
+        assert_eq!(rand.seed(), 10);
+        assert_eq!(rand.seed_source(), SeedSource::Explicit);
+
+        rand.set_seed(20);
+        assert_eq!(rand.seed(), 20);
+        assert_eq!(rand.seed_source(), SeedSource::Explicit);
+
+        assert_eq!(seed_from_env_value("1234"), Ok(1234));
+        assert_eq!(seed_from_env_value(" 1234\n"), Ok(1234));
+        assert!(seed_from_env_value("not a seed").is_err());
+        assert!(seed_from_env_value("-1").is_err());
+
+        // The environment is passed in so that the test does not depend on it.
+        assert_eq!(Rand::<Mt19937GenRand32>::choose_seed(Some("1234")).1, SeedSource::Environment);
+        assert_eq!(
+            Rand::<Mt19937GenRand32>::choose_seed(Some("not a seed")).1,
+            SeedSource::InvalidEnvironment,
+        );
+        assert_eq!(Rand::<Mt19937GenRand32>::choose_seed(None).1, SeedSource::Generated);
+
+        // Sources seeded from the environment do not share a seed.
+        assert_eq!(derive_seed(1234, 0), 1234);
+        assert_eq!(derive_seed(1234, 5), derive_seed(1234, 5));
+        let seeds = (0..100)
+            .map(|index| derive_seed(1234, index))
+            .collect::<HashSet<_>>();
+        assert_eq!(seeds.len(), 100);
+        assert_ne!(derive_seed(1234, 1), derive_seed(1235, 1));
+
+        // Sources created back-to-back should not share a seed.
+        let seeds = iter::repeat_with(|| Rand::<Mt19937GenRand32>::choose_seed(None))
+            .take(100)
+            .inspect(|&(_, source)| assert_eq!(source, SeedSource::Generated))
+            .map(|(seed, _)| seed)
+            .collect::<HashSet<_>>();
+        assert_eq!(seeds.len(), 100);
     }
 }
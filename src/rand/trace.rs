@@ -0,0 +1,445 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Tracing of random number draws
+//!
+//! When two runs of a game which should be identical diverge, the first draw from a random number
+//! source which differs usually points at the culprit. This module records draws along with the
+//! location in the code which requested them so that two traces may be compared.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, Write};
+use std::panic::Location;
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single draw from a random number source.
+pub struct RandTraceEntry {
+    /// The index of the draw since tracing was enabled.
+    pub index: u64,
+    /// The value which was drawn.
+    pub value: u32,
+    /// The file which requested the draw.
+    pub file: Cow<'static, str>,
+    /// The line which requested the draw.
+    pub line: u32,
+    /// The column which requested the draw.
+    pub column: u32,
+}
+
+impl RandTraceEntry {
+    /// Whether two entries are the same draw from the same location.
+    fn same_draw(&self, other: &Self) -> bool {
+        self.value == other.value
+            && self.file == other.file
+            && self.line == other.line
+            && self.column == other.column
+    }
+}
+
+impl Display for RandTraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}:{}:{}",
+            self.index, self.value, self.file, self.line, self.column,
+        )
+    }
+}
+
+/// Errors which may occur when reading a trace.
+#[derive(Debug, Error)]
+pub enum RandTraceError {
+    /// An error reading the trace.
+    #[error("failed to read the trace: {}", _0)]
+    Io(#[from] io::Error),
+    /// A line in the trace could not be parsed.
+    #[error("invalid trace entry on line {}: {:?}", line, content)]
+    InvalidEntry {
+        /// The line number of the entry.
+        line: usize,
+        /// The content of the line.
+        content: String,
+    },
+}
+
+/// A bounded log of draws from a random number source.
+///
+/// Only the most recent draws are kept once the capacity has been reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RandTrace {
+    /// The recorded draws.
+    entries: VecDeque<RandTraceEntry>,
+    /// The maximum number of draws to keep.
+    capacity: usize,
+    /// The index of the next draw.
+    next_index: u64,
+}
+
+impl RandTrace {
+    /// Create a new trace which keeps at most `capacity` draws.
+    pub fn new(capacity: usize) -> Self {
+        RandTrace {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            next_index: 0,
+        }
+    }
+
+    /// Record a draw.
+    pub(crate) fn record(&mut self, location: &'static Location<'static>, value: u32) {
+        if self.capacity == 0 {
+            self.next_index += 1;
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(RandTraceEntry {
+            index: self.next_index,
+            value,
+            file: Cow::Borrowed(location.file()),
+            line: location.line(),
+            column: location.column(),
+        });
+        self.next_index += 1;
+    }
+
+    #[inline]
+    /// The recorded draws, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &RandTraceEntry> {
+        self.entries.iter()
+    }
+
+    #[inline]
+    /// The total number of draws seen, including those which are no longer kept.
+    pub fn draw_count(&self) -> u64 {
+        self.next_index
+    }
+
+    /// The index of the oldest draw which is kept.
+    fn window_start(&self) -> u64 {
+        self.entries
+            .front()
+            .map_or(self.next_index, |entry| entry.index)
+    }
+
+    /// The kept draw with a given index.
+    fn entry(&self, index: u64) -> Option<&RandTraceEntry> {
+        let offset = index.checked_sub(self.window_start())?;
+        self.entries.get(offset as usize)
+    }
+
+    /// Find the first draw which differs between two traces.
+    ///
+    /// Only draws which are present in both traces are compared; if one trace has kept draws from
+    /// further back, those are ignored. If one trace ends before the other, the first draw missing
+    /// from the shorter trace is reported. This is also the case when the shorter trace ends
+    /// before the kept draws of the other begin, in which case neither draw is available.
+    pub fn first_divergence(&self, other: &Self) -> Option<RandDivergence> {
+        let start = self.window_start().max(other.window_start());
+        let end = self.next_index.min(other.next_index);
+
+        let mismatch = (start..end).find(|&index| {
+            match (self.entry(index), other.entry(index)) {
+                (Some(left), Some(right)) => !left.same_draw(right),
+                _ => true,
+            }
+        });
+        // A trace which ends early diverges at its end.
+        let ended = if self.next_index == other.next_index {
+            None
+        } else {
+            Some(end)
+        };
+
+        mismatch.or(ended).map(|index| {
+            RandDivergence {
+                index,
+                left: self.entry(index).cloned(),
+                right: other.entry(index).cloned(),
+            }
+        })
+    }
+
+    /// Write the trace in a text format.
+    ///
+    /// Each draw is written on its own line as its index, value, and location separated by tabs.
+    pub fn write<W>(&self, mut out: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.entries
+            .iter()
+            .try_for_each(|entry| writeln!(out, "{}", entry))
+    }
+
+    /// Read a trace written by `RandTrace::write`.
+    ///
+    /// The indices of the draws must be consecutive.
+    pub fn read<R>(input: R) -> Result<Self, RandTraceError>
+    where
+        R: BufRead,
+    {
+        let mut expected_index = None;
+        let entries = input
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| {
+                let line_no = idx + 1;
+                let content = line?;
+                let entry = Self::parse_entry(&content).filter(|entry| {
+                    expected_index.is_none() || expected_index == Some(entry.index)
+                });
+                match entry {
+                    Some(entry) => {
+                        expected_index = entry.index.checked_add(1);
+                        Ok(entry)
+                    },
+                    None => {
+                        Err(RandTraceError::InvalidEntry {
+                            line: line_no,
+                            content,
+                        })
+                    },
+                }
+            })
+            .collect::<Result<VecDeque<_>, _>>()?;
+
+        let next_index = entries.back().map_or(0, |entry| entry.index + 1);
+
+        Ok(RandTrace {
+            capacity: entries.len(),
+            entries,
+            next_index,
+        })
+    }
+
+    fn parse_entry(content: &str) -> Option<RandTraceEntry> {
+        let mut fields = content.splitn(3, '\t');
+        let index = fields.next()?.parse().ok()?;
+        let value = fields.next()?.parse().ok()?;
+
+        let mut location = fields.next()?.rsplitn(3, ':');
+        let column = location.next()?.parse().ok()?;
+        let line = location.next()?.parse().ok()?;
+        let file = location.next()?.to_string();
+
+        Some(RandTraceEntry {
+            index,
+            value,
+            file: Cow::Owned(file),
+            line,
+            column,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The first draw which differs between two traces.
+pub struct RandDivergence {
+    /// The index of the draw which differs.
+    pub index: u64,
+    /// The draw in the first trace, if it was recorded.
+    pub left: Option<RandTraceEntry>,
+    /// The draw in the second trace, if it was recorded.
+    pub right: Option<RandTraceEntry>,
+}
+
+impl Display for RandDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn describe(entry: &Option<RandTraceEntry>) -> String {
+            entry.as_ref().map_or_else(
+                || "no draw".into(),
+                |entry| {
+                    format!(
+                        "{} at {}:{}:{}",
+                        entry.value, entry.file, entry.line, entry.column,
+                    )
+                },
+            )
+        }
+
+        write!(
+            f,
+            "draw {} differs: {} vs. {}",
+            self.index,
+            describe(&self.left),
+            describe(&self.right),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::iter;
+
+    use crate::rand::{Rand, RandTrace, RandTraceError};
+
+    fn traced_rand(seed: u32, capacity: usize) -> Rand {
+        let mut rand = Rand::with_seed(seed);
+        rand.enable_trace(capacity);
+        rand
+    }
+
+    #[test]
+    fn test_trace_does_not_change_sequence() {
+        let mut rand: Rand = Rand::with_seed(1);
+        let mut traced = traced_rand(1, 5);
+
+        let values = iter::repeat_with(|| (rand.next_int(100), rand.next_float(1.)))
+            .take(20)
+            .collect::<Vec<_>>();
+        let traced_values = iter::repeat_with(|| (traced.next_int(100), traced.next_float(1.)))
+            .take(20)
+            .collect::<Vec<_>>();
+
+        assert_eq!(values, traced_values);
+    }
+
+    #[test]
+    fn test_trace_records_call_sites() {
+        let mut rand = traced_rand(1, 10);
+
+        let value = rand.next_u32();
+        rand.next_float(1.);
+        let mut items = [1, 2, 3];
+        rand.shuffle(&mut items);
+
+        let trace = rand.disable_trace().unwrap();
+        assert!(rand.trace().is_none());
+        assert_eq!(trace.draw_count(), 4);
+
+        let entries = trace.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].value, value);
+        entries.iter().enumerate().for_each(|(idx, entry)| {
+            assert_eq!(entry.index, idx as u64);
+            assert!(entry.file.ends_with("trace.rs"), "{}", entry.file);
+        });
+        assert!(entries[0].line < entries[1].line);
+        assert!(entries[1].line < entries[2].line);
+        assert_eq!(entries[2].line, entries[3].line);
+    }
+
+    #[test]
+    fn test_trace_is_bounded() {
+        let mut rand = traced_rand(1, 3);
+        iter::repeat_with(|| rand.next_u32())
+            .take(10)
+            .for_each(drop);
+
+        let trace = rand.trace().unwrap();
+        assert_eq!(trace.draw_count(), 10);
+        assert_eq!(
+            trace.entries().map(|entry| entry.index).collect::<Vec<_>>(),
+            vec![7, 8, 9],
+        );
+
+        let mut rand = traced_rand(1, 0);
+        rand.next_u32();
+        assert_eq!(rand.trace().unwrap().draw_count(), 1);
+        assert_eq!(rand.trace().unwrap().entries().count(), 0);
+    }
+
+    fn run(seed_change: Option<(usize, u32)>, capacity: usize, count: usize) -> RandTrace {
+        let mut rand = traced_rand(1, capacity);
+
+        (0..count).for_each(|i| {
+            if let Some((at, seed)) = seed_change {
+                if at == i {
+                    rand.set_seed(seed);
+                }
+            }
+            rand.next_int(1000);
+        });
+
+        rand.disable_trace().unwrap()
+    }
+
+    #[test]
+    fn test_trace_divergence() {
+        assert_eq!(run(None, 20, 10).first_divergence(&run(None, 20, 10)), None);
+        assert_eq!(RandTrace::new(5).first_divergence(&RandTrace::new(5)), None,);
+
+        let divergence = run(None, 20, 10)
+            .first_divergence(&run(Some((6, 2)), 20, 10))
+            .unwrap();
+        assert_eq!(divergence.index, 6);
+        assert_eq!(divergence.left.as_ref().unwrap().index, 6);
+        assert_ne!(
+            divergence.left.as_ref().unwrap().value,
+            divergence.right.as_ref().unwrap().value,
+        );
+
+        // Traces which have dropped different amounts of history are compared where they overlap.
+        let divergence = run(None, 5, 10)
+            .first_divergence(&run(Some((8, 2)), 8, 10))
+            .unwrap();
+        assert_eq!(divergence.index, 8);
+
+        // A trace which ends early diverges at its end.
+        let divergence = run(None, 20, 10)
+            .first_divergence(&run(None, 20, 7))
+            .unwrap();
+        assert_eq!(divergence.index, 7);
+        assert!(divergence.left.is_some());
+        assert!(divergence.right.is_none());
+
+        // A trace which ends before the other's kept draws begin diverges at its end, even though
+        // neither trace has the draw.
+        let divergence = run(None, 3, 5).first_divergence(&run(None, 3, 20)).unwrap();
+        assert_eq!(divergence.index, 5);
+        assert!(divergence.left.is_none());
+        assert!(divergence.right.is_none());
+        let divergence = run(None, 3, 20).first_divergence(&run(None, 3, 5)).unwrap();
+        assert_eq!(divergence.index, 5);
+
+        // Traces which keep nothing only differ in their length.
+        assert_eq!(run(None, 0, 10).first_divergence(&run(None, 5, 10)), None);
+        let divergence = run(None, 0, 100)
+            .first_divergence(&run(None, 5, 10))
+            .unwrap();
+        assert_eq!(divergence.index, 10);
+    }
+
+    #[test]
+    fn test_trace_round_trip() {
+        let trace = run(None, 5, 10);
+
+        let mut buffer = Vec::new();
+        trace.write(&mut buffer).unwrap();
+        let read = RandTrace::read(buffer.as_slice()).unwrap();
+
+        assert_eq!(read.draw_count(), trace.draw_count());
+        assert!(read.entries().eq(trace.entries()));
+        assert_eq!(read.first_divergence(&trace), None);
+    }
+
+    #[test]
+    fn test_trace_read_invalid() {
+        let err = RandTrace::read("0\t1\tsrc/main.rs:1:2\nbogus\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "invalid trace entry on line 2: \"bogus\"",);
+
+        // Gaps and reordered draws are rejected.
+        let err = RandTrace::read("3\t1\tsrc/main.rs:1:2\n5\t2\tsrc/main.rs:1:2\n".as_bytes())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid trace entry on line 2: \"5\\t2\\tsrc/main.rs:1:2\"",
+        );
+        let err = RandTrace::read("3\t1\tsrc/main.rs:1:2\n2\t2\tsrc/main.rs:1:2\n".as_bytes())
+            .unwrap_err();
+        assert!(matches!(err, RandTraceError::InvalidEntry { line: 2, .. }));
+
+        // Traces may start at any draw.
+        let read = RandTrace::read("3\t1\tsrc/main.rs:1:2\n4\t2\tsrc/main.rs:1:2\n".as_bytes())
+            .unwrap();
+        assert_eq!(read.draw_count(), 5);
+    }
+}
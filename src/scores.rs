@@ -0,0 +1,486 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! High score tables
+//!
+//! Each game mode and difficulty has its own table of the best scores. Tables are stored in the
+//! scores directory rather than directly in the data directory so that they may be found, backed
+//! up, or overridden on their own; it is always within the data directory. Tables are written
+//! atomically and carry a checksum so that corrupt or hand-edited files are detected. The checksum
+//! is not cryptographic; it deters casual editing only.
+
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, TimeZone, Utc};
+use thiserror::Error;
+
+use crate::atomic::write_atomic;
+use crate::paths::Paths;
+
+/// The header of a score file.
+const HEADER: &str = "abagames-scores 1";
+/// The prefix of the checksum line.
+const CHECKSUM_PREFIX: &str = "checksum ";
+
+/// Errors which may occur when loading scores.
+#[derive(Debug, Error)]
+pub enum ScoresError {
+    /// An error reading the file.
+    #[error("failed to read scores: {}", _0)]
+    Io(#[from] io::Error),
+    /// The file is not a score file.
+    #[error("invalid score file header: {:?}", _0)]
+    InvalidHeader(String),
+    /// The checksum line is missing.
+    #[error("the score checksum is missing")]
+    MissingChecksum,
+    /// The checksum is not a hexadecimal number.
+    #[error("invalid score checksum: {:?}", _0)]
+    InvalidChecksum(String),
+    /// The checksum does not match the contents.
+    #[error("the score checksum does not match")]
+    ChecksumMismatch,
+    /// An entry could not be parsed.
+    #[error("invalid score entry on line {}: {:?}", line, content)]
+    InvalidEntry {
+        /// The line number of the entry.
+        line: usize,
+        /// The content of the line.
+        content: String,
+    },
+    /// A mode or difficulty is not usable as part of a file name.
+    #[error("invalid score table name: {:?}", _0)]
+    InvalidTableName(String),
+}
+
+/// A convenience alias for score results.
+pub type ScoresResult<T> = Result<T, ScoresError>;
+
+/// Compute the checksum of score file contents.
+///
+/// This is the 64-bit FNV-1a hash.
+fn checksum(content: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    content.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Replace control characters in a name with spaces so that it fits on a single line.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry in a high score table.
+///
+/// Control characters in names are replaced with spaces when they are stored.
+pub struct ScoreEntry {
+    /// The name of the player.
+    pub name: String,
+    /// The score.
+    pub score: u64,
+    /// The stage reached.
+    pub stage: u32,
+    /// When the score was achieved.
+    pub date: DateTime<Utc>,
+}
+
+impl ScoreEntry {
+    /// Create a new entry achieved now.
+    pub fn new<N>(name: N, score: u64, stage: u32) -> Self
+    where
+        N: Into<String>,
+    {
+        Self::with_date(name, score, stage, Utc::now())
+    }
+
+    /// Create a new entry achieved at a given time.
+    ///
+    /// Control characters in the name are replaced with spaces. The date is stored with a
+    /// resolution of seconds.
+    pub fn with_date<N>(name: N, score: u64, stage: u32, date: DateTime<Utc>) -> Self
+    where
+        N: Into<String>,
+    {
+        let name = sanitize_name(&name.into());
+        let date = Utc
+            .timestamp_opt(date.timestamp(), 0)
+            .single()
+            .unwrap_or(date);
+
+        ScoreEntry {
+            name,
+            score,
+            stage,
+            date,
+        }
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.score,
+            self.stage,
+            self.date.timestamp(),
+            sanitize_name(&self.name),
+        )
+    }
+
+    fn parse(content: &str) -> Option<Self> {
+        let mut fields = content.splitn(4, '\t');
+        let score = fields.next()?.parse().ok()?;
+        let stage = fields.next()?.parse().ok()?;
+        let date = Utc
+            .timestamp_opt(fields.next()?.parse().ok()?, 0)
+            .single()?;
+        let name = fields.next()?.into();
+
+        Some(ScoreEntry {
+            name,
+            score,
+            stage,
+            date,
+        })
+    }
+}
+
+/// A ranked table of high scores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoreTable {
+    /// The entries, best first.
+    entries: Vec<ScoreEntry>,
+    /// The maximum number of entries.
+    capacity: usize,
+}
+
+impl ScoreTable {
+    /// Create an empty table holding at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        ScoreTable {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    #[inline]
+    /// The entries in the table, best first.
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    #[inline]
+    /// The maximum number of entries in the table.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The rank a score would achieve, if it would make it into the table.
+    ///
+    /// Ranks start at `1`. Ties rank below existing entries.
+    pub fn rank_of(&self, score: u64) -> Option<usize> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.score < score)
+            .unwrap_or(self.entries.len());
+
+        if index < self.capacity {
+            Some(index + 1)
+        } else {
+            None
+        }
+    }
+
+    /// Insert an entry into the table.
+    ///
+    /// Returns the rank achieved, starting at `1`, or `None` if the score did not make it into
+    /// the table. The lowest entry is dropped if the table is full.
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let rank = self.rank_of(entry.score)?;
+
+        self.entries.insert(rank - 1, entry);
+        self.entries.truncate(self.capacity);
+
+        Some(rank)
+    }
+
+    /// The path to the table for a mode and difficulty.
+    ///
+    /// Each mode has its own directory with a table per difficulty.
+    ///
+    /// Modes and difficulties may only contain ASCII letters, digits, hyphens, and underscores so
+    /// that they cannot escape the scores directory.
+    pub fn path(paths: &Paths, mode: &str, difficulty: &str) -> ScoresResult<PathBuf> {
+        let is_valid = |name: &str| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if let Some(name) = [mode, difficulty].iter().find(|name| !is_valid(name)) {
+            return Err(ScoresError::InvalidTableName((*name).into()));
+        }
+
+        Ok(paths
+            .scores_dir
+            .join(mode)
+            .join(format!("{}.txt", difficulty)))
+    }
+
+    /// Parse a table.
+    ///
+    /// Entries beyond `capacity` are dropped.
+    pub fn parse(content: &str, capacity: usize) -> ScoresResult<Self> {
+        let checksum_start = content
+            .rfind(CHECKSUM_PREFIX)
+            .ok_or(ScoresError::MissingChecksum)?;
+        let (body, checksum_line) = content.split_at(checksum_start);
+        let expected = checksum_line[CHECKSUM_PREFIX.len()..].trim();
+        let expected = u64::from_str_radix(expected, 16)
+            .map_err(|_| ScoresError::InvalidChecksum(expected.into()))?;
+        if checksum(body) != expected {
+            return Err(ScoresError::ChecksumMismatch);
+        }
+
+        let mut lines = body.lines();
+        let header = lines.next().unwrap_or_default();
+        if header != HEADER {
+            return Err(ScoresError::InvalidHeader(header.into()));
+        }
+
+        let mut entries = lines
+            .enumerate()
+            .map(|(idx, line)| {
+                ScoreEntry::parse(line).ok_or_else(|| {
+                    ScoresError::InvalidEntry {
+                        line: idx + 2,
+                        content: line.into(),
+                    }
+                })
+            })
+            .collect::<ScoresResult<Vec<_>>>()?;
+        entries.sort_by_key(|entry| Reverse(entry.score));
+        entries.truncate(capacity);
+
+        Ok(ScoreTable {
+            entries,
+            capacity,
+        })
+    }
+
+    /// Format the table for storage.
+    pub fn format(&self) -> String {
+        let mut body = format!("{}\n", HEADER);
+        self.entries.iter().for_each(|entry| {
+            body.push_str(&entry.format());
+            body.push('\n');
+        });
+
+        let sum = checksum(&body);
+        format!("{}{}{:016x}\n", body, CHECKSUM_PREFIX, sum)
+    }
+
+    /// Load a table from a file.
+    ///
+    /// A missing file is an empty table.
+    pub fn load<P>(path: P, capacity: usize) -> ScoresResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content, capacity),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::new(capacity)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Save a table to a file.
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        write_atomic(path, self.format())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{TimeZone, Utc};
+
+    use crate::paths::Paths;
+    use crate::scores::{ScoreEntry, ScoreTable, ScoresError};
+    use crate::test_util::TempDir;
+
+    fn entry(name: &str, score: u64) -> ScoreEntry {
+        ScoreEntry::with_date(name, score, 3, Utc.timestamp_opt(1_000_000, 0).unwrap())
+    }
+
+    #[test]
+    fn test_score_table_insert() {
+        let mut table = ScoreTable::new(3);
+
+        assert_eq!(table.insert(entry("a", 100)), Some(1));
+        assert_eq!(table.insert(entry("b", 300)), Some(1));
+        assert_eq!(table.insert(entry("c", 200)), Some(2));
+        // Ties rank below existing entries.
+        assert_eq!(table.rank_of(200), Some(3));
+        assert_eq!(table.insert(entry("d", 200)), Some(3));
+        assert_eq!(table.rank_of(100), None);
+        assert_eq!(table.insert(entry("e", 50)), None);
+
+        assert_eq!(
+            table
+                .entries()
+                .iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["b", "c", "d"],
+        );
+
+        let mut empty = ScoreTable::new(0);
+        assert_eq!(empty.insert(entry("a", 100)), None);
+    }
+
+    #[test]
+    fn test_score_entry_sanitizes() {
+        let date = Utc.timestamp_opt(1_000_000, 500).unwrap();
+        let sanitized = ScoreEntry::with_date("a\tb\nc", 1, 1, date);
+        assert_eq!(sanitized.name, "a b c");
+        assert_eq!(sanitized.date, Utc.timestamp_opt(1_000_000, 0).unwrap());
+
+        // Entries built directly are sanitized when they are stored.
+        let mut table = ScoreTable::new(2);
+        table.insert(ScoreEntry {
+            name: "a\tb\nc".into(),
+            score: 10,
+            stage: 2,
+            date: Utc.timestamp_opt(1_000_000, 0).unwrap(),
+        });
+        table.insert(entry("d", 5));
+        let table = ScoreTable::parse(&table.format(), 2).unwrap();
+        assert_eq!(table.entries()[0].name, "a b c");
+        assert_eq!(table.entries()[1].name, "d");
+    }
+
+    #[test]
+    fn test_score_table_round_trip() {
+        let mut table = ScoreTable::new(5);
+        table.insert(entry("alice", 100));
+        table.insert(entry("bob smith", 200));
+
+        let content = table.format();
+        assert_eq!(ScoreTable::parse(&content, 5).unwrap(), table);
+
+        // A smaller capacity drops the lowest entries.
+        let smaller = ScoreTable::parse(&content, 1).unwrap();
+        assert_eq!(smaller.entries().len(), 1);
+        assert_eq!(smaller.entries()[0].name, "bob smith");
+    }
+
+    #[test]
+    fn test_score_table_corruption() {
+        let mut table = ScoreTable::new(5);
+        table.insert(entry("alice", 100));
+        let content = table.format();
+
+        let tampered = content.replace("100", "900");
+        assert!(matches!(
+            ScoreTable::parse(&tampered, 5),
+            Err(ScoresError::ChecksumMismatch),
+        ));
+
+        let truncated = &content[..content.len() / 2];
+        assert!(matches!(
+            ScoreTable::parse(truncated, 5),
+            Err(ScoresError::MissingChecksum),
+        ));
+
+        assert!(matches!(
+            ScoreTable::parse("", 5),
+            Err(ScoresError::MissingChecksum),
+        ));
+
+        let garbled = content.replace("checksum ", "checksum zz");
+        let err = ScoreTable::parse(&garbled, 5).unwrap_err();
+        assert!(matches!(err, ScoresError::InvalidChecksum(_)));
+        assert!(err.to_string().starts_with("invalid score checksum: \"zz"));
+    }
+
+    #[test]
+    fn test_score_table_load_and_save() {
+        let temp = TempDir::new("scores");
+        let root = temp.path();
+        let paths = Paths::for_test(root);
+
+        let path = ScoreTable::path(&paths, "normal", "hard").unwrap();
+        assert_eq!(path, root.join("scores").join("normal").join("hard.txt"));
+        assert_eq!(ScoreTable::load(&path, 10).unwrap(), ScoreTable::new(10));
+
+        let mut table = ScoreTable::new(10);
+        table.insert(entry("alice", 100));
+        table.save(&path).unwrap();
+        assert_eq!(ScoreTable::load(&path, 10).unwrap(), table);
+        assert!(root
+            .join("scores")
+            .join("normal")
+            .join("hard.txt")
+            .is_file());
+    }
+
+    #[test]
+    fn test_score_table_separate_names() {
+        let temp = TempDir::new("scores-names");
+        let paths = Paths::for_test(temp.path());
+
+        let first = ScoreTable::path(&paths, "a-b", "c").unwrap();
+        let second = ScoreTable::path(&paths, "a", "b-c").unwrap();
+
+        let mut table = ScoreTable::new(10);
+        table.insert(entry("alice", 100));
+        table.save(&first).unwrap();
+
+        assert_eq!(ScoreTable::load(&first, 10).unwrap(), table);
+        assert_eq!(ScoreTable::load(&second, 10).unwrap(), ScoreTable::new(10));
+    }
+
+    #[test]
+    fn test_score_table_path_names() {
+        let paths = Paths::for_test("source");
+
+        assert!(ScoreTable::path(&paths, "time_attack", "2").is_ok());
+        assert!(ScoreTable::path(&paths, "twin-stick", "normal").is_ok());
+        assert_ne!(
+            ScoreTable::path(&paths, "a-b", "c").unwrap(),
+            ScoreTable::path(&paths, "a", "b-c").unwrap(),
+        );
+        for &(mode, difficulty) in &[
+            ("../x", "hard"),
+            ("normal", "a/b"),
+            ("", "hard"),
+            ("normal", "a b"),
+            ("normal", "."),
+        ] {
+            let err = ScoreTable::path(&paths, mode, difficulty).unwrap_err();
+            assert!(
+                matches!(err, ScoresError::InvalidTableName(_)),
+                "{} {}",
+                mode,
+                difficulty,
+            );
+        }
+        assert_eq!(
+            ScoreTable::path(&paths, "../x", "hard")
+                .unwrap_err()
+                .to_string(),
+            "invalid score table name: \"../x\"",
+        );
+    }
+}
@@ -6,16 +6,20 @@ use sdl2::mixer::{self, Sdl2MixerContext};
 use sdl2::rwops::RWops;
 use sdl2::{self, Sdl};
 
+use crate::assets::Asset;
+
 pub mod audio;
 pub mod error;
 pub mod input;
 pub mod mainloop;
+pub mod projection;
 pub mod video;
 
 pub use self::audio::Audio;
 pub use self::error::*;
 pub use self::input::{Input, Scancode};
 pub use self::mainloop::{Event, Game, MainLoop, StepResult};
+pub use self::projection::Projection;
 pub use self::video::{EncoderContext, EncoderDrawContext, Resources, TargetFormat, Video};
 
 /// SDL subsystem structure.
@@ -79,6 +83,8 @@ impl<'a> SdlBuilder<'a> {
     }
 
     /// Load audio from data.
+    ///
+    /// Use `with_music_assets` to allow the data to be replaced by files on disk.
     pub fn with_music<M>(&mut self, music: M) -> &mut Self
     where
         M: IntoIterator<Item = &'a (&'a str, &'a [u8])>,
@@ -91,6 +97,8 @@ impl<'a> SdlBuilder<'a> {
     }
 
     /// Load audio from data.
+    ///
+    /// Use `with_sfx_assets` to allow the data to be replaced by files on disk.
     pub fn with_sfx<S>(&mut self, sfx: S) -> &mut Self
     where
         S: IntoIterator<Item = &'a (&'a str, &'a [u8], i32)>,
@@ -102,6 +110,32 @@ impl<'a> SdlBuilder<'a> {
         self
     }
 
+    /// Load music from assets, such as those returned by `AssetResolver::load_all`.
+    pub fn with_music_assets<M>(&mut self, music: M) -> &mut Self
+    where
+        M: IntoIterator<Item = &'a (&'a str, Asset<'a>)>,
+    {
+        self.music_data = music
+            .into_iter()
+            .map(|(name, asset)| (*name, RWops::from_bytes(asset.bytes()).unwrap()))
+            .collect();
+        self
+    }
+
+    /// Load sound effects from assets, each with the channel to play it on.
+    pub fn with_sfx_assets<S>(&mut self, sfx: S) -> &mut Self
+    where
+        S: IntoIterator<Item = &'a (&'a str, Asset<'a>, i32)>,
+    {
+        self.sfx_data = sfx
+            .into_iter()
+            .map(|(name, asset, channel)| {
+                (*name, RWops::from_bytes(asset.bytes()).unwrap(), *channel)
+            })
+            .collect();
+        self
+    }
+
     /// Construct the subsystem structure and the main loop.
     pub fn build(&mut self) -> SdlResult<(SdlInfo, MainLoop)> {
         let audio = if self.audio {
@@ -0,0 +1,216 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Mapping between world and screen coordinates
+//!
+//! Screen coordinates are in pixels with the origin in the top left corner and y increasing
+//! downward, matching the orthographic matrix used for the HUD. Screen depths range from `0` at
+//! the near plane to `1` at the far plane.
+
+use cgmath::{Matrix4, SquareMatrix, Vector2, Vector3, Vector4};
+
+/// Whether every entry of a matrix is finite.
+fn is_finite(matrix: &Matrix4<f32>) -> bool {
+    AsRef::<[f32; 16]>::as_ref(matrix)
+        .iter()
+        .all(|value| value.is_finite())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A projection from world coordinates onto the screen.
+pub struct Projection {
+    /// The matrix mapping world coordinates to clip coordinates.
+    matrix: Matrix4<f32>,
+    /// The inverse of `matrix`.
+    inverse: Matrix4<f32>,
+    /// The size of the screen.
+    size: Vector2<f32>,
+}
+
+impl Projection {
+    /// Create a projection for a screen of the given size.
+    ///
+    /// The matrix maps world coordinates to clip coordinates, e.g., a perspective matrix
+    /// multiplied by a view matrix. Returns `None` if the screen has no area or the matrix is not
+    /// finite and invertible.
+    pub fn new(matrix: Matrix4<f32>, size: Vector2<u32>) -> Option<Self> {
+        if size.x == 0 || size.y == 0 || !is_finite(&matrix) {
+            return None;
+        }
+
+        matrix.invert().filter(is_finite).map(|inverse| {
+            Projection {
+                matrix,
+                inverse,
+                size: Vector2::new(size.x as f32, size.y as f32),
+            }
+        })
+    }
+
+    #[inline]
+    /// The matrix mapping world coordinates to clip coordinates.
+    pub fn matrix(&self) -> &Matrix4<f32> {
+        &self.matrix
+    }
+
+    #[inline]
+    /// The size of the screen.
+    pub fn size(&self) -> Vector2<f32> {
+        self.size
+    }
+
+    /// Project a point in the world onto the screen.
+    ///
+    /// The result holds the screen position and depth of the point. Returns `None` for points
+    /// behind the viewer. Points outside of the screen are still projected.
+    pub fn project(&self, point: Vector3<f32>) -> Option<Vector3<f32>> {
+        let clip = self.matrix * point.extend(1.);
+        if clip.w <= 0. {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+        Some(Vector3::new(
+            (ndc.x + 1.) / 2. * self.size.x,
+            (1. - ndc.y) / 2. * self.size.y,
+            (ndc.z + 1.) / 2.,
+        ))
+    }
+
+    /// Map a screen position and depth back into the world.
+    ///
+    /// This is the inverse of `project`.
+    pub fn unproject(&self, screen: Vector2<f32>, depth: f32) -> Vector3<f32> {
+        let ndc = Vector4::new(
+            screen.x / self.size.x * 2. - 1.,
+            1. - screen.y / self.size.y * 2.,
+            depth * 2. - 1.,
+            1.,
+        );
+        let world = self.inverse * ndc;
+
+        world.truncate() / world.w
+    }
+
+    /// The ray through a screen position.
+    ///
+    /// The ray starts on the near plane and its direction reaches the far plane.
+    pub fn screen_ray(&self, screen: Vector2<f32>) -> (Vector3<f32>, Vector3<f32>) {
+        let near = self.unproject(screen, 0.);
+        let far = self.unproject(screen, 1.);
+
+        (near, far - near)
+    }
+
+    /// Find where the ray through a screen position meets the plane at the given `z`.
+    ///
+    /// Returns `None` if the ray is parallel to the plane or the plane is behind the near plane.
+    pub fn cast_to_z_plane(&self, screen: Vector2<f32>, z: f32) -> Option<Vector3<f32>> {
+        let (origin, direction) = self.screen_ray(screen);
+        if direction.z == 0. {
+            return None;
+        }
+
+        let t = (z - origin.z) / direction.z;
+        if t < 0. {
+            None
+        } else {
+            Some(origin + direction * t)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{InnerSpace, Matrix4, Vector2, Vector3};
+
+    use crate::sdl::projection::Projection;
+
+    fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>) {
+        assert!(
+            (actual - expected).magnitude() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected,
+        );
+    }
+
+    fn projection() -> Projection {
+        let size = Vector2::new(640, 480);
+        let aspect = 480. / 640.;
+        let matrix = cgmath::frustum(-0.1, 0.1, -0.1 * aspect, 0.1 * aspect, 0.1, 1000.)
+            * Matrix4::from_translation(Vector3::new(0., 0., -20.));
+
+        Projection::new(matrix, size).unwrap()
+    }
+
+    #[test]
+    fn test_project() {
+        let projection = projection();
+
+        // The center of the view is the center of the screen.
+        let center = projection.project(Vector3::new(0., 0., 0.)).unwrap();
+        assert!((center.x - 320.).abs() < 1e-3);
+        assert!((center.y - 240.).abs() < 1e-3);
+        assert!(0. < center.z && center.z < 1.);
+
+        // Up and right in the world are up and right on the screen.
+        let corner = projection.project(Vector3::new(1., 1., 0.)).unwrap();
+        assert!(corner.x > 320.);
+        assert!(corner.y < 240.);
+
+        // Points behind the viewer are not projected.
+        assert_eq!(projection.project(Vector3::new(0., 0., 30.)), None);
+    }
+
+    #[test]
+    fn test_unproject() {
+        let projection = projection();
+
+        [
+            Vector3::new(0., 0., 0.),
+            Vector3::new(3., -2., 1.),
+            Vector3::new(-10., 5., -50.),
+        ]
+        .iter()
+        .for_each(|&point| {
+            let screen = projection.project(point).unwrap();
+            assert_close(projection.unproject(screen.truncate(), screen.z), point);
+        });
+    }
+
+    #[test]
+    fn test_cast_to_z_plane() {
+        let projection = projection();
+
+        let point = Vector3::new(4., -3., 0.);
+        let screen = projection.project(point).unwrap().truncate();
+        assert_close(projection.cast_to_z_plane(screen, 0.).unwrap(), point);
+
+        let hit = projection
+            .cast_to_z_plane(Vector2::new(320., 240.), -5.)
+            .unwrap();
+        assert_close(hit, Vector3::new(0., 0., -5.));
+
+        // The plane is behind the viewer.
+        assert_eq!(projection.cast_to_z_plane(screen, 30.), None);
+    }
+
+    #[test]
+    fn test_singular_matrix() {
+        assert!(Projection::new(Matrix4::from_scale(0.), Vector2::new(640, 480)).is_none());
+        assert!(
+            Projection::new(Matrix4::from_scale(f32::INFINITY), Vector2::new(640, 480)).is_none()
+        );
+    }
+
+    #[test]
+    fn test_empty_screen() {
+        let matrix = *projection().matrix();
+
+        assert!(Projection::new(matrix, Vector2::new(640, 480)).is_some());
+        assert!(Projection::new(matrix, Vector2::new(0, 480)).is_none());
+        assert!(Projection::new(matrix, Vector2::new(640, 0)).is_none());
+        assert!(Projection::new(matrix, Vector2::new(0, 0)).is_none());
+    }
+}
@@ -9,7 +9,7 @@
 
 use std::marker::PhantomData;
 
-use cgmath::{self, Matrix4, Vector2};
+use cgmath::{self, Matrix4, Vector2, Vector3};
 use gfx;
 use gfx::format::{DepthStencil, Srgba8};
 use gfx::handle::{DepthStencilView, RenderTargetView};
@@ -21,6 +21,7 @@ use sdl2::video::{GLContext, GLProfile, Window};
 use sdl2::Sdl;
 
 use crate::sdl::error::*;
+use crate::sdl::projection::Projection;
 
 pub use gfx_device_gl::{Factory, Resources};
 /// The specialized encoder type for the games.
@@ -84,6 +85,7 @@ pub struct Video<'a> {
     size: Vector2<u32>,
     perspective_matrix: Matrix4<f32>,
     orthographic_matrix: Matrix4<f32>,
+    projection: Option<Projection>,
 
     _phantom: PhantomData<&'a str>,
 }
@@ -147,11 +149,13 @@ impl<'a> Video<'a> {
         sdl_context.mouse().show_cursor(false);
 
         let win_size = window.size().into();
+        let perspective_matrix = Self::calc_perspective_matrix(win_size);
 
         Ok(Video {
             size: win_size,
-            perspective_matrix: Self::calc_perspective_matrix(win_size),
+            perspective_matrix,
             orthographic_matrix: Self::calc_orthographic_matrix(win_size),
+            projection: Projection::new(perspective_matrix, win_size),
 
             encoder: factory.create_command_buffer().into(),
 
@@ -188,6 +192,7 @@ impl<'a> Video<'a> {
         self.size = size;
         self.perspective_matrix = Self::calc_perspective_matrix(size);
         self.orthographic_matrix = Self::calc_orthographic_matrix(size);
+        self.projection = Projection::new(self.perspective_matrix, size);
     }
 
     /// The perspective matrix for the window.
@@ -195,6 +200,48 @@ impl<'a> Video<'a> {
         &self.perspective_matrix
     }
 
+    /// The orthographic matrix for the window.
+    pub fn orthographic_matrix(&self) -> &Matrix4<f32> {
+        &self.orthographic_matrix
+    }
+
+    /// The size of the window.
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    /// The projection from world coordinates onto the window.
+    ///
+    /// The `view` matrix is applied before the perspective matrix. Returns `None` if the view
+    /// matrix is singular or the window has no area.
+    pub fn projection(&self, view: Matrix4<f32>) -> Option<Projection> {
+        Projection::new(self.perspective_matrix * view, self.size)
+    }
+
+    /// Project a point in the untransformed world onto the window.
+    ///
+    /// Returns `None` if the window has no area. See `Projection::project`.
+    pub fn project(&self, point: Vector3<f32>) -> Option<Vector3<f32>> {
+        self.projection
+            .and_then(|projection| projection.project(point))
+    }
+
+    /// Map a window position and depth back into the untransformed world.
+    ///
+    /// Returns `None` if the window has no area. See `Projection::unproject`.
+    pub fn unproject(&self, screen: Vector2<f32>, depth: f32) -> Option<Vector3<f32>> {
+        self.projection
+            .map(|projection| projection.unproject(screen, depth))
+    }
+
+    /// Find where the ray through a window position meets the plane at the given `z`.
+    ///
+    /// Returns `None` if the window has no area. See `Projection::cast_to_z_plane`.
+    pub fn cast_to_z_plane(&self, screen: Vector2<f32>, z: f32) -> Option<Vector3<f32>> {
+        self.projection
+            .and_then(|projection| projection.cast_to_z_plane(screen, z))
+    }
+
     /// The factory for handling resources with the device.
     pub fn factory(&mut self) -> &mut Factory {
         &mut self.factory
@@ -0,0 +1,748 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Settings persistence
+//!
+//! Settings are stored in the configuration directory as `key = value` lines along with a schema
+//! version. Files written by older versions of a game are migrated when they are loaded. Files
+//! which are missing or corrupt are replaced by the defaults, but files written by newer versions
+//! are left alone.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use cgmath::Vector2;
+use thiserror::Error;
+
+use crate::atomic::write_atomic;
+use crate::paths::{Paths, PathsDir};
+
+/// The key holding the schema version.
+const VERSION_KEY: &str = "version";
+/// Characters which are escaped in keys in addition to those escaped in values.
+const KEY_SPECIAL: &[char] = &['=', '#'];
+
+/// Errors which may occur when loading settings.
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    /// An error reading the file.
+    #[error("failed to read settings: {}", _0)]
+    Io(#[from] io::Error),
+    /// A line could not be parsed.
+    #[error("invalid settings line {}: {:?}", line, content)]
+    InvalidLine {
+        /// The line number.
+        line: usize,
+        /// The content of the line.
+        content: String,
+    },
+    /// The schema version is missing.
+    #[error("the settings version is missing")]
+    MissingVersion,
+    /// The file was written by a newer version of the game.
+    #[error(
+        "unsupported settings version {} (expected at most {})",
+        found,
+        supported
+    )]
+    UnsupportedVersion {
+        /// The version of the file.
+        found: u32,
+        /// The newest supported version.
+        supported: u32,
+    },
+    /// A value could not be parsed.
+    #[error("invalid value for {}: {:?}", key, value)]
+    InvalidValue {
+        /// The key of the value.
+        key: String,
+        /// The value.
+        value: String,
+    },
+}
+
+/// A convenience alias for settings results.
+pub type SettingsResult<T> = Result<T, SettingsError>;
+
+/// The raw contents of a settings file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SettingsFile {
+    /// The schema version.
+    version: u32,
+    /// The values in the file.
+    values: BTreeMap<String, String>,
+}
+
+impl SettingsFile {
+    /// Create an empty settings file.
+    pub fn new(version: u32) -> Self {
+        SettingsFile {
+            version,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Parse a settings file.
+    ///
+    /// Blank lines and lines starting with `#` are ignored. Keys end at the first unescaped `=`.
+    pub fn parse(content: &str) -> SettingsResult<Self> {
+        let mut values = content
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_no, line)| {
+                match split_key(line) {
+                    Some((key, value)) if !key.trim().is_empty() => {
+                        Ok((
+                            unescape(key.trim(), KEY_SPECIAL),
+                            unescape(value.trim(), &[]),
+                        ))
+                    },
+                    _ => {
+                        Err(SettingsError::InvalidLine {
+                            line: line_no,
+                            content: line.into(),
+                        })
+                    },
+                }
+            })
+            .collect::<SettingsResult<BTreeMap<_, _>>>()?;
+
+        let version = values
+            .remove(VERSION_KEY)
+            .ok_or(SettingsError::MissingVersion)?;
+        let version = version.parse().map_err(|_| {
+            SettingsError::InvalidValue {
+                key: VERSION_KEY.into(),
+                value: version,
+            }
+        })?;
+
+        Ok(SettingsFile {
+            version,
+            values,
+        })
+    }
+
+    #[inline]
+    /// The schema version of the file.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    #[inline]
+    /// Set the schema version of the file.
+    pub fn set_version(&mut self, version: u32) {
+        self.version = version;
+    }
+
+    /// Get a value.
+    ///
+    /// Returns `None` if the value is missing.
+    pub fn get<T>(&self, key: &str) -> SettingsResult<Option<T>>
+    where
+        T: FromStr,
+    {
+        self.values
+            .get(key)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    SettingsError::InvalidValue {
+                        key: key.into(),
+                        value: value.clone(),
+                    }
+                })
+            })
+            .transpose()
+    }
+
+    /// Get a value, falling back to a default if it is missing.
+    pub fn get_or<T>(&self, key: &str, default: T) -> SettingsResult<T>
+    where
+        T: FromStr,
+    {
+        self.get(key).map(|value| value.unwrap_or(default))
+    }
+
+    /// Set a value.
+    ///
+    /// Line breaks and backslashes are escaped when the file is written; leading and trailing
+    /// whitespace in the value is not preserved. Keys are escaped the same way, along with `=` and
+    /// `#`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is empty, has leading or trailing whitespace or is the reserved `version`
+    /// key; use `set_version` instead.
+    pub fn set<T>(&mut self, key: &str, value: T)
+    where
+        T: Display,
+    {
+        check_key(key);
+        self.values.insert(key.into(), value.to_string());
+    }
+
+    /// Remove a value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.values.remove(key)
+    }
+
+    /// Rename a key.
+    ///
+    /// This is useful when migrating from older versions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new key is invalid; see `set`.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        check_key(to);
+        if let Some(value) = self.values.remove(from) {
+            self.values.insert(to.into(), value);
+        }
+    }
+
+    /// The values with keys starting with a prefix, with the prefix removed.
+    pub fn with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.values
+            .iter()
+            .filter(move |(key, _)| key.starts_with(prefix))
+            .map(move |(key, value)| (&key[prefix.len()..], value.as_str()))
+    }
+}
+
+impl Display for SettingsFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} = {}", VERSION_KEY, self.version)?;
+        self.values
+            .iter()
+            .try_for_each(|(key, value)| {
+                writeln!(f, "{} = {}", escape(key, KEY_SPECIAL), escape(value, &[]))
+            })
+    }
+}
+
+/// Check that a key may be stored in a settings file.
+fn check_key(key: &str) {
+    assert!(
+        !key.is_empty() && key.trim() == key,
+        "invalid settings key {:?}: keys must be non-empty without surrounding whitespace",
+        key,
+    );
+    assert!(
+        key != VERSION_KEY,
+        "the {:?} settings key is reserved for the schema version",
+        VERSION_KEY,
+    );
+}
+
+/// Split a line at the first unescaped `=`.
+fn split_key(line: &str) -> Option<(&str, &str)> {
+    let mut is_escaped = false;
+    line.char_indices()
+        .find(|&(_, c)| {
+            let is_split = !is_escaped && c == '=';
+            is_escaped = !is_escaped && c == '\\';
+            is_split
+        })
+        .map(|(idx, _)| (&line[..idx], &line[idx + 1..]))
+}
+
+/// Escape a value so that it fits on a single line.
+///
+/// Any `special` characters are escaped as well.
+fn escape(value: &str, special: &[char]) -> String {
+    value
+        .chars()
+        .fold(String::with_capacity(value.len()), |mut escaped, c| {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                c if special.contains(&c) => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                },
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+/// Undo `escape`.
+///
+/// Unknown escapes are kept as they are.
+fn unescape(value: &str, special: &[char]) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) if special.contains(&c) => unescaped.push(c),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            },
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// A typed set of settings which may be persisted.
+pub trait Settings: Default {
+    /// The name of the file within the configuration directory.
+    const FILE_NAME: &'static str;
+    /// The current schema version.
+    const VERSION: u32;
+
+    /// Read settings from a file of the current version.
+    fn read(file: &SettingsFile) -> SettingsResult<Self>;
+    /// Write settings to a file.
+    fn write(&self, file: &mut SettingsFile);
+
+    /// Migrate a file from an older version.
+    ///
+    /// This is called with the file's version until it reaches the current version. The version
+    /// must be increased by the migration.
+    fn migrate(file: &mut SettingsFile) -> SettingsResult<()> {
+        file.set_version(Self::VERSION);
+        Ok(())
+    }
+
+    /// Parse settings, migrating them if necessary.
+    fn parse(content: &str) -> SettingsResult<Self> {
+        let mut file = SettingsFile::parse(content)?;
+
+        if file.version() > Self::VERSION {
+            return Err(SettingsError::UnsupportedVersion {
+                found: file.version(),
+                supported: Self::VERSION,
+            });
+        }
+        while file.version() < Self::VERSION {
+            let version = file.version();
+            Self::migrate(&mut file)?;
+            assert!(
+                file.version() > version,
+                "settings migration did not increase the version",
+            );
+        }
+
+        Self::read(&file)
+    }
+
+    /// Format settings for storage.
+    fn format(&self) -> String {
+        let mut file = SettingsFile::new(Self::VERSION);
+        self.write(&mut file);
+        file.to_string()
+    }
+
+    /// The path to the settings file.
+    fn path(paths: &Paths) -> PathBuf {
+        paths.config_dir.join(Self::FILE_NAME)
+    }
+
+    /// Load settings from the configuration directory.
+    ///
+    /// Returns `None` if there is no settings file.
+    fn try_load(paths: &Paths) -> SettingsResult<Option<Self>> {
+        match fs::read_to_string(Self::path(paths)) {
+            Ok(content) => Self::parse(&content).map(Some),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Load settings from the configuration directory.
+    ///
+    /// Missing or corrupt settings are replaced by the defaults; the error which caused corrupt
+    /// settings to be replaced is returned alongside them so that it may be reported. Settings
+    /// which cannot be read or were written by a newer version of the game are errors so that
+    /// saving the defaults does not overwrite them.
+    fn load(paths: &Paths) -> SettingsResult<(Self, Option<SettingsError>)> {
+        match Self::try_load(paths) {
+            Ok(settings) => Ok((settings.unwrap_or_default(), None)),
+            // The defaults would replace settings which may still be read.
+            Err(err)
+                if matches!(
+                    err,
+                    SettingsError::Io(_) | SettingsError::UnsupportedVersion { .. }
+                ) =>
+            {
+                Err(err)
+            },
+            Err(err) => Ok((Self::default(), Some(err))),
+        }
+    }
+
+    /// Save settings to the configuration directory.
+    fn save(&self, paths: &Paths) -> io::Result<()> {
+        paths.create_dir(PathsDir::Config)?;
+        write_atomic(Self::path(paths), self.format())
+    }
+}
+
+/// The prefix for key binding settings.
+const KEY_PREFIX: &str = "key.";
+
+/// Settings common to the games.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSettings {
+    /// The size of the window.
+    pub window_size: Vector2<u32>,
+    /// Whether to run in a window rather than fullscreen.
+    pub windowed: bool,
+    /// Whether music is enabled.
+    pub music_enabled: bool,
+    /// The music volume, from `0` to `1`.
+    pub music_volume: f32,
+    /// Whether sound effects are enabled.
+    pub sfx_enabled: bool,
+    /// The sound effect volume, from `0` to `1`.
+    pub sfx_volume: f32,
+    /// The brightness of the display.
+    pub brightness: f32,
+    /// Key bindings from action names to SDL scancode names.
+    pub key_bindings: BTreeMap<String, String>,
+    /// The game mode last selected on the title screen.
+    pub last_mode: Option<String>,
+    /// The difficulty last selected on the title screen.
+    pub last_difficulty: Option<String>,
+    /// The starting stage last selected on the title screen.
+    pub last_stage: Option<u32>,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            window_size: Vector2::new(640, 480),
+            windowed: false,
+            music_enabled: true,
+            music_volume: 1.,
+            sfx_enabled: true,
+            sfx_volume: 1.,
+            brightness: 1.,
+            key_bindings: BTreeMap::new(),
+            last_mode: None,
+            last_difficulty: None,
+            last_stage: None,
+        }
+    }
+}
+
+impl Settings for GameSettings {
+    const FILE_NAME: &'static str = "settings.txt";
+    const VERSION: u32 = 1;
+
+    fn read(file: &SettingsFile) -> SettingsResult<Self> {
+        let defaults = Self::default();
+
+        Ok(GameSettings {
+            window_size: Vector2::new(
+                file.get_or("window.width", defaults.window_size.x)?,
+                file.get_or("window.height", defaults.window_size.y)?,
+            ),
+            windowed: file.get_or("window.windowed", defaults.windowed)?,
+            music_enabled: file.get_or("music.enabled", defaults.music_enabled)?,
+            music_volume: file.get_or("music.volume", defaults.music_volume)?,
+            sfx_enabled: file.get_or("sfx.enabled", defaults.sfx_enabled)?,
+            sfx_volume: file.get_or("sfx.volume", defaults.sfx_volume)?,
+            brightness: file.get_or("brightness", defaults.brightness)?,
+            key_bindings: file
+                .with_prefix(KEY_PREFIX)
+                .map(|(action, key)| (action.into(), key.into()))
+                .collect(),
+            last_mode: file.get("last.mode")?,
+            last_difficulty: file.get("last.difficulty")?,
+            last_stage: file.get("last.stage")?,
+        })
+    }
+
+    fn write(&self, file: &mut SettingsFile) {
+        file.set("window.width", self.window_size.x);
+        file.set("window.height", self.window_size.y);
+        file.set("window.windowed", self.windowed);
+        file.set("music.enabled", self.music_enabled);
+        file.set("music.volume", self.music_volume);
+        file.set("sfx.enabled", self.sfx_enabled);
+        file.set("sfx.volume", self.sfx_volume);
+        file.set("brightness", self.brightness);
+        self.key_bindings.iter().for_each(|(action, key)| {
+            file.set(&format!("{}{}", KEY_PREFIX, action), key);
+        });
+        if let Some(mode) = self.last_mode.as_ref() {
+            file.set("last.mode", mode);
+        }
+        if let Some(difficulty) = self.last_difficulty.as_ref() {
+            file.set("last.difficulty", difficulty);
+        }
+        if let Some(stage) = self.last_stage {
+            file.set("last.stage", stage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use cgmath::Vector2;
+
+    use crate::paths::Paths;
+    use crate::settings::{GameSettings, Settings, SettingsError, SettingsFile, SettingsResult};
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_settings_file_parse() {
+        let file = SettingsFile::parse(
+            "# comment\n\nversion = 3\n  a.b = some value  \nkey.fire=Z\nkey.bomb = X\nkeyz = 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(file.version(), 3);
+        assert_eq!(file.get::<String>("a.b").unwrap().unwrap(), "some value");
+        assert_eq!(file.get::<u32>("missing").unwrap(), None);
+        assert_eq!(file.get_or("missing", 5).unwrap(), 5);
+        assert_eq!(
+            file.with_prefix("key.").collect::<Vec<_>>(),
+            vec![("bomb", "X"), ("fire", "Z")],
+        );
+        assert_eq!(
+            file.get::<u32>("a.b").unwrap_err().to_string(),
+            "invalid value for a.b: \"some value\"",
+        );
+
+        let round_trip = SettingsFile::parse(&file.to_string()).unwrap();
+        assert_eq!(round_trip, file);
+    }
+
+    #[test]
+    fn test_settings_file_escapes() {
+        let mut file = SettingsFile::new(1);
+        file.set("multi", "first\nsecond\r\nthird");
+        file.set("path", "C:\\new\\");
+        file.set("next", 1);
+
+        let content = file.to_string();
+        assert_eq!(content.lines().count(), 4);
+        assert!(content.contains("multi = first\\nsecond\\r\\nthird\n"));
+        assert!(content.contains("path = C:\\\\new\\\\\n"));
+
+        let round_trip = SettingsFile::parse(&content).unwrap();
+        assert_eq!(round_trip, file);
+        assert_eq!(
+            round_trip.get::<String>("multi").unwrap().unwrap(),
+            "first\nsecond\r\nthird",
+        );
+        assert_eq!(round_trip.get::<u32>("next").unwrap(), Some(1));
+
+        // Unknown escapes are kept.
+        let file = SettingsFile::parse("version = 1\na = \\t\\\n").unwrap();
+        assert_eq!(file.get::<String>("a").unwrap().unwrap(), "\\t\\");
+    }
+
+    #[test]
+    fn test_settings_file_key_escapes() {
+        let mut file = SettingsFile::new(2);
+        file.set("key.a=b", "equals");
+        file.set("#hash", "comment");
+        file.set("line\nbreak", "break");
+        file.set("back\\slash=", "slash");
+        file.set("version.", 3);
+        file.set("next", "x = y");
+
+        let content = file.to_string();
+        assert_eq!(content.lines().count(), 7);
+        assert!(content.starts_with("version = 2\n"));
+        assert!(content.contains("key.a\\=b = equals\n"));
+        assert!(content.contains("\\#hash = comment\n"));
+        assert!(content.contains("line\\nbreak = break\n"));
+        assert!(content.contains("back\\\\slash\\= = slash\n"));
+
+        let round_trip = SettingsFile::parse(&content).unwrap();
+        assert_eq!(round_trip, file);
+        assert_eq!(round_trip.version(), 2);
+        assert_eq!(
+            round_trip.get::<String>("key.a=b").unwrap().unwrap(),
+            "equals",
+        );
+        assert_eq!(round_trip.get::<String>("#hash").unwrap().unwrap(), "comment");
+        assert_eq!(
+            round_trip.get::<String>("back\\slash=").unwrap().unwrap(),
+            "slash",
+        );
+        assert_eq!(round_trip.get::<String>("next").unwrap().unwrap(), "x = y");
+    }
+
+    #[test]
+    #[should_panic(expected = "reserved for the schema version")]
+    fn test_settings_file_version_key() {
+        SettingsFile::new(1).set("version", 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "keys must be non-empty")]
+    fn test_settings_file_empty_key() {
+        SettingsFile::new(1).set(" ", 2);
+    }
+
+    #[test]
+    fn test_settings_file_parse_errors() {
+        let err = SettingsFile::parse("version = 1\nbogus\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid settings line 2: \"bogus\"");
+
+        let err = SettingsFile::parse("version = 1\n= value\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid settings line 2: \"= value\"");
+
+        let err = SettingsFile::parse("a = 1\n").unwrap_err();
+        assert!(matches!(err, SettingsError::MissingVersion));
+
+        let err = SettingsFile::parse("version = one\n").unwrap_err();
+        assert_eq!(err.to_string(), "invalid value for version: \"one\"");
+    }
+
+    #[test]
+    fn test_game_settings_round_trip() {
+        let mut settings = GameSettings {
+            window_size: Vector2::new(1280, 960),
+            windowed: true,
+            sfx_volume: 0.25,
+            brightness: 0.75,
+            last_difficulty: Some("hard".into()),
+            last_stage: Some(6),
+            ..GameSettings::default()
+        };
+        settings
+            .key_bindings
+            .insert("fire".into(), "Left Ctrl".into());
+
+        let content = settings.format();
+        assert!(content.starts_with("version = 1\n"));
+        assert!(content.contains("key.fire = Left Ctrl\n"));
+        assert!(content.contains("last.stage = 6\n"));
+        assert!(!content.contains("last.mode"));
+        assert_eq!(GameSettings::parse(&content).unwrap(), settings);
+
+        // Missing values use the defaults.
+        assert_eq!(
+            GameSettings::parse("version = 1\n").unwrap(),
+            GameSettings::default(),
+        );
+
+        let err = GameSettings::parse("version = 2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported settings version 2 (expected at most 1)",
+        );
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Versioned {
+        volume: u32,
+    }
+
+    impl Settings for Versioned {
+        const FILE_NAME: &'static str = "versioned.txt";
+        const VERSION: u32 = 3;
+
+        fn read(file: &SettingsFile) -> SettingsResult<Self> {
+            Ok(Versioned {
+                volume: file.get_or("volume", 0)?,
+            })
+        }
+
+        fn write(&self, file: &mut SettingsFile) {
+            file.set("volume", self.volume);
+        }
+
+        fn migrate(file: &mut SettingsFile) -> SettingsResult<()> {
+            match file.version() {
+                // Version 2 renamed the key.
+                1 => file.rename("sound", "volume"),
+                // Version 3 changed from a fraction of 10 to a percentage.
+                2 => {
+                    let volume: u32 = file.get_or("volume", 0)?;
+                    file.set("volume", volume * 10);
+                },
+                _ => unreachable!(),
+            }
+            file.set_version(file.version() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_settings_migration() {
+        assert_eq!(
+            Versioned::parse("version = 1\nsound = 7\n").unwrap(),
+            Versioned {
+                volume: 70
+            },
+        );
+        assert_eq!(
+            Versioned::parse("version = 2\nvolume = 7\n").unwrap(),
+            Versioned {
+                volume: 70
+            },
+        );
+        assert_eq!(
+            Versioned::parse("version = 3\nvolume = 7\n").unwrap(),
+            Versioned {
+                volume: 7
+            },
+        );
+    }
+
+    #[test]
+    fn test_settings_load_and_save() {
+        let temp = TempDir::new("settings");
+        let root = temp.path();
+        let paths = Paths::for_test(root);
+
+        // Missing settings use the defaults.
+        assert!(GameSettings::try_load(&paths).unwrap().is_none());
+        let (loaded, err) = GameSettings::load(&paths).unwrap();
+        assert_eq!(loaded, GameSettings::default());
+        assert!(err.is_none());
+
+        let settings = GameSettings {
+            music_enabled: false,
+            ..GameSettings::default()
+        };
+        settings.save(&paths).unwrap();
+        assert_eq!(GameSettings::load(&paths).unwrap().0, settings);
+        assert!(root.join("settings.txt").is_file());
+
+        // Corrupt settings use the defaults.
+        fs::write(root.join("settings.txt"), "garbage").unwrap();
+        assert!(GameSettings::try_load(&paths).is_err());
+        let (loaded, err) = GameSettings::load(&paths).unwrap();
+        assert_eq!(loaded, GameSettings::default());
+        assert!(matches!(err, Some(SettingsError::InvalidLine { line: 1, .. })));
+
+        // Settings from a newer version are not replaced.
+        fs::write(root.join("settings.txt"), "version = 2\n").unwrap();
+        assert!(matches!(
+            GameSettings::load(&paths),
+            Err(SettingsError::UnsupportedVersion {
+                found: 2,
+                supported: 1,
+            }),
+        ));
+    }
+}
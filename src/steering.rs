@@ -0,0 +1,499 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Steering behaviours
+//!
+//! These compute the motion of homing shots and enemies which chase, flee from, or circle a
+//! target. Each behaviour determines a desired velocity and then turns and accelerates the
+//! current velocity toward it within the limits of the mover. Limits are per game step so that
+//! behaviour is deterministic.
+
+use cgmath::{InnerSpace, Vector2, Zero};
+
+use crate::math::{
+    angle_difference, angle_to_vector, normalize_angle, rotate_toward, vector_to_angle,
+};
+use crate::rand::{Rand, RandBackend};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The direction to circle around an orbit.
+///
+/// Directions are in terms of the angles used by `angle_to_vector`.
+pub enum OrbitDirection {
+    /// Circle in the direction of increasing angle.
+    Positive,
+    /// Circle in the direction of decreasing angle.
+    Negative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The motion limits of a steered object.
+pub struct Steering {
+    /// The maximum speed.
+    pub max_speed: f32,
+    /// The maximum change in speed per step.
+    pub max_acceleration: f32,
+    /// The maximum change in heading per step, in radians.
+    pub max_turn: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The state of a wandering object.
+///
+/// Wandering steers toward a point on a circle ahead of the object. The point jitters randomly
+/// around the circle each step, giving smooth but unpredictable motion.
+pub struct Wander {
+    /// The distance of the circle ahead of the object.
+    pub distance: f32,
+    /// The radius of the circle.
+    pub radius: f32,
+    /// The maximum change in the angle of the point on the circle per step, in radians.
+    pub jitter: f32,
+    /// The angle of the point on the circle relative to the heading, in `[-π, π)`.
+    angle: f32,
+}
+
+impl Wander {
+    /// Create a new wander state.
+    pub fn new(distance: f32, radius: f32, jitter: f32) -> Self {
+        Wander {
+            distance,
+            radius,
+            jitter,
+            angle: 0.,
+        }
+    }
+
+    #[inline]
+    /// The angle of the point on the circle relative to the heading, in `[-π, π)`.
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+}
+
+impl Steering {
+    /// Create a new set of steering limits.
+    pub fn new(max_speed: f32, max_acceleration: f32, max_turn: f32) -> Self {
+        Steering {
+            max_speed,
+            max_acceleration,
+            max_turn,
+        }
+    }
+
+    /// Turn and accelerate a velocity toward a desired velocity.
+    ///
+    /// The heading turns by at most `max_turn` and the speed changes by at most
+    /// `max_acceleration`. The resulting speed never exceeds `max_speed`. A stationary object may
+    /// start moving in any direction.
+    pub fn steer(&self, velocity: Vector2<f32>, desired: Vector2<f32>) -> Vector2<f32> {
+        let speed = velocity.magnitude();
+        let desired_speed = desired.magnitude().min(self.max_speed);
+
+        let speed_change = desired_speed - speed;
+        let new_speed = if speed_change.abs() <= self.max_acceleration {
+            desired_speed
+        } else {
+            speed + self.max_acceleration.copysign(speed_change)
+        };
+
+        let heading = if desired.is_zero() {
+            if velocity.is_zero() {
+                return Vector2::zero();
+            }
+            vector_to_angle(velocity)
+        } else if velocity.is_zero() {
+            vector_to_angle(desired)
+        } else {
+            rotate_toward(
+                vector_to_angle(velocity),
+                vector_to_angle(desired),
+                self.max_turn,
+            )
+        };
+
+        angle_to_vector(heading) * new_speed
+    }
+
+    #[inline]
+    /// The change in heading to turn toward a target, limited by `max_turn`.
+    ///
+    /// This is suitable for homing shots which move at a fixed speed along their heading.
+    pub fn turn_toward(&self, angle: f32, position: Vector2<f32>, target: Vector2<f32>) -> f32 {
+        if position == target {
+            return 0.;
+        }
+
+        let diff = angle_difference(angle, vector_to_angle(target - position));
+        diff.max(-self.max_turn).min(self.max_turn)
+    }
+
+    #[inline]
+    /// Move toward a target at full speed.
+    pub fn seek(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        target: Vector2<f32>,
+    ) -> Vector2<f32> {
+        self.steer(velocity, self.full_speed(target - position))
+    }
+
+    #[inline]
+    /// Move away from a target at full speed.
+    pub fn flee(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        target: Vector2<f32>,
+    ) -> Vector2<f32> {
+        self.steer(velocity, self.full_speed(position - target))
+    }
+
+    /// Move toward a target, slowing down to stop on it.
+    ///
+    /// The object slows once it is within `slowing_radius` of the target.
+    pub fn arrive(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        target: Vector2<f32>,
+        slowing_radius: f32,
+    ) -> Vector2<f32> {
+        let offset = target - position;
+        let distance = offset.magnitude();
+
+        let desired = if distance < slowing_radius {
+            offset * (self.max_speed / slowing_radius)
+        } else {
+            self.full_speed(offset)
+        };
+
+        self.steer(velocity, desired)
+    }
+
+    /// Move toward where a moving target will be.
+    ///
+    /// The target is assumed to keep its velocity for as long as it would take to reach its
+    /// current position. Objects which cannot move head for the current position.
+    pub fn pursue(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        target: Vector2<f32>,
+        target_velocity: Vector2<f32>,
+    ) -> Vector2<f32> {
+        let predicted = self.predict(position, target, target_velocity);
+        self.seek(position, velocity, predicted)
+    }
+
+    /// Move away from where a moving target will be.
+    ///
+    /// See `pursue` for how the target is predicted.
+    pub fn evade(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        target: Vector2<f32>,
+        target_velocity: Vector2<f32>,
+    ) -> Vector2<f32> {
+        let predicted = self.predict(position, target, target_velocity);
+        self.flee(position, velocity, predicted)
+    }
+
+    /// Circle around a center at a given radius.
+    ///
+    /// Objects away from the orbit spiral in or out toward it. A radius of zero or less seeks the
+    /// center.
+    pub fn orbit(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        center: Vector2<f32>,
+        radius: f32,
+        direction: OrbitDirection,
+    ) -> Vector2<f32> {
+        if radius <= 0. {
+            return self.seek(position, velocity, center);
+        }
+
+        let offset = position - center;
+        let distance = offset.magnitude();
+        if distance == 0. {
+            return self.seek(position, velocity, center + Vector2::unit_y() * radius);
+        }
+
+        let radial = offset / distance;
+        let tangent = match direction {
+            OrbitDirection::Positive => Vector2::new(radial.y, -radial.x),
+            OrbitDirection::Negative => Vector2::new(-radial.y, radial.x),
+        };
+        let correction = radial * ((radius - distance) / radius);
+
+        self.steer(velocity, self.full_speed(tangent + correction))
+    }
+
+    /// Wander randomly.
+    pub fn wander<B>(
+        &self,
+        velocity: Vector2<f32>,
+        wander: &mut Wander,
+        rand: &mut Rand<B>,
+    ) -> Vector2<f32>
+    where
+        B: RandBackend,
+    {
+        wander.angle = normalize_angle(wander.angle + rand.next_float_signed(wander.jitter));
+
+        let heading = if velocity.is_zero() {
+            0.
+        } else {
+            vector_to_angle(velocity)
+        };
+        let desired = angle_to_vector(heading) * wander.distance
+            + angle_to_vector(heading + wander.angle) * wander.radius;
+
+        self.steer(velocity, self.full_speed(desired))
+    }
+
+    #[inline]
+    /// A velocity at full speed in the direction of `offset`.
+    fn full_speed(&self, offset: Vector2<f32>) -> Vector2<f32> {
+        if offset.is_zero() {
+            offset
+        } else {
+            offset.normalize_to(self.max_speed)
+        }
+    }
+
+    #[inline]
+    /// Predict the future position of a moving target.
+    fn predict(
+        &self,
+        position: Vector2<f32>,
+        target: Vector2<f32>,
+        target_velocity: Vector2<f32>,
+    ) -> Vector2<f32> {
+        if self.max_speed <= 0. {
+            return target;
+        }
+
+        let time = (target - position).magnitude() / self.max_speed;
+        target + target_velocity * time
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use cgmath::{InnerSpace, Vector2, Zero};
+
+    use crate::math::{angle_difference, vector_to_angle};
+    use crate::rand::Rand;
+    use crate::steering::{OrbitDirection, Steering, Wander};
+
+    fn assert_close(actual: Vector2<f32>, expected: Vector2<f32>) {
+        assert!(
+            (actual - expected).magnitude() < 1e-4,
+            "{:?} != {:?}",
+            actual,
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_steer_limits() {
+        let steering = Steering::new(2., 0.5, 0.1);
+
+        // A stationary object starts in the desired direction, limited by acceleration.
+        assert_close(
+            steering.steer(Vector2::zero(), Vector2::new(10., 0.)),
+            Vector2::new(0.5, 0.),
+        );
+        // Speed is capped.
+        assert_close(
+            steering.steer(Vector2::new(1.8, 0.), Vector2::new(10., 0.)),
+            Vector2::new(2., 0.),
+        );
+        // Turning is limited.
+        let velocity = steering.steer(Vector2::new(0., 1.), Vector2::new(10., 0.));
+        assert!((vector_to_angle(velocity) - 0.1).abs() < 1e-6);
+        assert!((velocity.magnitude() - 1.5).abs() < 1e-6);
+        // Stopping keeps the heading.
+        assert_close(
+            steering.steer(Vector2::new(0., 1.), Vector2::zero()),
+            Vector2::new(0., 0.5),
+        );
+        assert_close(
+            steering.steer(Vector2::zero(), Vector2::zero()),
+            Vector2::zero(),
+        );
+    }
+
+    #[test]
+    fn test_turn_toward() {
+        let steering = Steering::new(1., 1., 0.25);
+        let position = Vector2::new(1., 1.);
+
+        assert_eq!(steering.turn_toward(0., position, Vector2::new(1., 5.)), 0.);
+        assert_eq!(
+            steering.turn_toward(0., position, Vector2::new(5., 1.)),
+            0.25,
+        );
+        assert_eq!(
+            steering.turn_toward(0., position, Vector2::new(-5., 1.)),
+            -0.25,
+        );
+        assert!(
+            (steering.turn_toward(FRAC_PI_2 - 0.1, position, Vector2::new(5., 1.)) - 0.1).abs()
+                < 1e-6
+        );
+        assert_eq!(steering.turn_toward(PI, position, position), 0.);
+    }
+
+    #[test]
+    fn test_seek_and_flee() {
+        let steering = Steering::new(1., 1., PI);
+        let position = Vector2::new(0., 0.);
+        let target = Vector2::new(3., 4.);
+
+        assert_close(
+            steering.seek(position, Vector2::zero(), target),
+            Vector2::new(0.6, 0.8),
+        );
+        assert_close(
+            steering.flee(position, Vector2::zero(), target),
+            Vector2::new(-0.6, -0.8),
+        );
+    }
+
+    #[test]
+    fn test_arrive() {
+        let steering = Steering::new(1., 1., PI);
+        let target = Vector2::new(10., 0.);
+
+        let mut position = Vector2::zero();
+        let mut velocity = Vector2::zero();
+        (0..100).for_each(|_| {
+            velocity = steering.arrive(position, velocity, target, 4.);
+            position += velocity;
+        });
+
+        assert_close(position, target);
+        assert!(velocity.magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn test_pursue_and_evade() {
+        let steering = Steering::new(1., 1., PI);
+        let position = Vector2::zero();
+        let target = Vector2::new(0., 4.);
+        let target_velocity = Vector2::new(0.75, 0.);
+
+        // Reaching the target takes 4 steps, by which time it will have moved by 3.
+        assert_close(
+            steering.pursue(position, Vector2::zero(), target, target_velocity),
+            Vector2::new(0.6, 0.8),
+        );
+        assert_close(
+            steering.evade(position, Vector2::zero(), target, target_velocity),
+            Vector2::new(-0.6, -0.8),
+        );
+    }
+
+    #[test]
+    fn test_orbit() {
+        let steering = Steering::new(0.1, 0.1, 0.5);
+        let center = Vector2::new(5., 5.);
+
+        let mut position = Vector2::new(5., 12.);
+        let mut velocity = Vector2::zero();
+        (0..2000).for_each(|_| {
+            velocity = steering.orbit(position, velocity, center, 4., OrbitDirection::Positive);
+            position += velocity;
+        });
+
+        assert!(((position - center).magnitude() - 4.).abs() < 0.1);
+        // Moving in the direction of increasing angle.
+        let radial = position - center;
+        assert!(radial.x * velocity.y - radial.y * velocity.x < 0.);
+
+        let velocity = steering.orbit(
+            position,
+            Vector2::zero(),
+            center,
+            4.,
+            OrbitDirection::Negative,
+        );
+        assert!(radial.x * velocity.y - radial.y * velocity.x > 0.);
+    }
+
+    #[test]
+    fn test_degenerate_limits() {
+        let steering = Steering::new(1., 1., PI);
+        let position = Vector2::new(3., 4.);
+
+        // A zero radius seeks the center.
+        assert_close(
+            steering.orbit(
+                position,
+                Vector2::zero(),
+                Vector2::zero(),
+                0.,
+                OrbitDirection::Positive,
+            ),
+            Vector2::new(-0.6, -0.8),
+        );
+
+        // A stationary object does not predict the target.
+        let stationary = Steering::new(0., 1., PI);
+        let target = Vector2::new(0., 4.);
+        let target_velocity = Vector2::new(1., 0.);
+        assert_eq!(
+            stationary.pursue(position, Vector2::zero(), target, target_velocity),
+            Vector2::zero(),
+        );
+        assert_eq!(
+            stationary.evade(position, Vector2::zero(), target, target_velocity),
+            Vector2::zero(),
+        );
+    }
+
+    #[test]
+    fn test_wander() {
+        let steering = Steering::new(1., 0.1, 0.2);
+        let mut wander = Wander::new(2., 1., 0.3);
+        let mut rand: Rand = Rand::with_seed(1);
+
+        let mut velocity = Vector2::new(0., 1.);
+        let mut angle = vector_to_angle(velocity);
+        (0..100).for_each(|_| {
+            velocity = steering.wander(velocity, &mut wander, &mut rand);
+            assert!((velocity.magnitude() - 1.).abs() < 1e-4);
+
+            let new_angle = vector_to_angle(velocity);
+            assert!(angle_difference(angle, new_angle).abs() <= 0.2 + 1e-6);
+            angle = new_angle;
+        });
+        assert_ne!(wander.angle(), 0.);
+        assert!(-PI <= wander.angle() && wander.angle() < PI);
+
+        // The angle stays bounded however long the object wanders.
+        let mut long_wander = Wander::new(2., 1., PI);
+        let mut long_velocity = velocity;
+        (0..10000).for_each(|_| {
+            long_velocity = steering.wander(long_velocity, &mut long_wander, &mut rand);
+            assert!(-PI <= long_wander.angle() && long_wander.angle() < PI);
+        });
+
+        // The same seed wanders the same way.
+        let mut other_wander = Wander::new(2., 1., 0.3);
+        let mut other_rand: Rand = Rand::with_seed(1);
+        let mut other_velocity = Vector2::new(0., 1.);
+        (0..100).for_each(|_| {
+            other_velocity = steering.wander(other_velocity, &mut other_wander, &mut other_rand);
+        });
+        assert_eq!(velocity, other_velocity);
+    }
+}
@@ -0,0 +1,36 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Utilities for tests
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A temporary directory which is removed when it is dropped, even if a test fails.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create an empty temporary directory.
+    ///
+    /// The name must be unique among the tests since they run concurrently.
+    pub(crate) fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("abagames-util-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    #[inline]
+    /// The path to the directory.
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
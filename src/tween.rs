@@ -0,0 +1,541 @@
+// Distributed under the OSI-approved BSD 2-Clause License.
+// See accompanying LICENSE file for details.
+
+//! Easing and tweening
+//!
+//! Easing curves map linear progress onto smoother motion. Tweens use them to interpolate values
+//! over a number of frames. Tweens are advanced once per game step rather than by wall-clock time
+//! so that they are deterministic.
+
+use std::f32::consts::PI;
+
+use cgmath::{Vector2, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Easing curves.
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Quadratic acceleration from zero velocity.
+    QuadIn,
+    /// Quadratic deceleration to zero velocity.
+    QuadOut,
+    /// Quadratic acceleration and deceleration.
+    QuadInOut,
+    /// Cubic acceleration from zero velocity.
+    CubicIn,
+    /// Cubic deceleration to zero velocity.
+    CubicOut,
+    /// Cubic acceleration and deceleration.
+    CubicInOut,
+    /// Sinusoidal acceleration from zero velocity.
+    SineIn,
+    /// Sinusoidal deceleration to zero velocity.
+    SineOut,
+    /// Sinusoidal acceleration and deceleration.
+    SineInOut,
+    /// Pull back slightly before moving.
+    BackIn,
+    /// Overshoot slightly before settling.
+    BackOut,
+    /// Pull back and overshoot.
+    BackInOut,
+    /// Oscillate with increasing amplitude.
+    ElasticIn,
+    /// Oscillate with decreasing amplitude.
+    ElasticOut,
+    /// Oscillate in and out.
+    ElasticInOut,
+    /// Bounce with increasing height.
+    BounceIn,
+    /// Bounce with decreasing height.
+    BounceOut,
+    /// Bounce in and out.
+    BounceInOut,
+}
+
+/// The overshoot of the back easings.
+const BACK_OVERSHOOT: f32 = 1.701_58;
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1. / D {
+        N * t * t
+    } else if t < 2. / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984_375
+    }
+}
+
+impl Easing {
+    /// Apply the easing to a progress value.
+    ///
+    /// The progress is clamped to `[0, 1]`. The result is `0` at the start and `1` at the end, but
+    /// may leave that range in between for the back and elastic easings.
+    pub fn apply(self, t: f32) -> f32 {
+        // `f32::clamp` is not available on Rust 1.47 and keeps NaN, which this treats as zero.
+        #[allow(clippy::manual_clamp)]
+        let t = t.max(0.).min(1.);
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1. - (1. - t) * (1. - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2. * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(2) / 2.
+                }
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1. - (1. - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 {
+                    4. * t * t * t
+                } else {
+                    1. - (-2. * t + 2.).powi(3) / 2.
+                }
+            },
+            Easing::SineIn => 1. - (t * PI / 2.).cos(),
+            Easing::SineOut => (t * PI / 2.).sin(),
+            Easing::SineInOut => -((PI * t).cos() - 1.) / 2.,
+            Easing::BackIn => {
+                let c = BACK_OVERSHOOT;
+                (c + 1.) * t * t * t - c * t * t
+            },
+            Easing::BackOut => {
+                let c = BACK_OVERSHOOT;
+                1. + (c + 1.) * (t - 1.).powi(3) + c * (t - 1.).powi(2)
+            },
+            Easing::BackInOut => {
+                let c = BACK_OVERSHOOT * 1.525;
+                if t < 0.5 {
+                    (2. * t).powi(2) * ((c + 1.) * 2. * t - c) / 2.
+                } else {
+                    ((2. * t - 2.).powi(2) * ((c + 1.) * (t * 2. - 2.) + c) + 2.) / 2.
+                }
+            },
+            Easing::ElasticIn => {
+                if t == 0. || t == 1. {
+                    t
+                } else {
+                    let c = 2. * PI / 3.;
+                    -(2_f32.powf(10. * t - 10.)) * ((t * 10. - 10.75) * c).sin()
+                }
+            },
+            Easing::ElasticOut => {
+                if t == 0. || t == 1. {
+                    t
+                } else {
+                    let c = 2. * PI / 3.;
+                    2_f32.powf(-10. * t) * ((t * 10. - 0.75) * c).sin() + 1.
+                }
+            },
+            Easing::ElasticInOut => {
+                if t == 0. || t == 1. {
+                    t
+                } else {
+                    let c = 2. * PI / 4.5;
+                    let s = ((20. * t - 11.125) * c).sin();
+                    if t < 0.5 {
+                        -(2_f32.powf(20. * t - 10.) * s) / 2.
+                    } else {
+                        2_f32.powf(-20. * t + 10.) * s / 2. + 1.
+                    }
+                }
+            },
+            Easing::BounceIn => 1. - bounce_out(1. - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => {
+                if t < 0.5 {
+                    (1. - bounce_out(1. - 2. * t)) / 2.
+                } else {
+                    (1. + bounce_out(2. * t - 1.)) / 2.
+                }
+            },
+        }
+    }
+}
+
+/// Values which may be interpolated by a tween.
+pub trait Tweenable: Clone {
+    /// Interpolate between two values.
+    ///
+    /// A `t` of `0` is `self` and `1` is `other`. Values outside of that range extrapolate.
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for Vector2<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for Vector3<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for [f32; 4] {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut out = *self;
+        out.iter_mut()
+            .zip(other.iter())
+            .for_each(|(a, b)| *a = a.lerp(b, t));
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a tween behaves once it reaches its end.
+pub enum TweenRepeat {
+    /// Stop at the end.
+    Once,
+    /// Start again from the beginning.
+    ///
+    /// The frame after the last one is the first frame of the next pass, so the end value is never
+    /// produced.
+    Loop,
+    /// Play backwards to the beginning and then forwards again.
+    Yoyo,
+}
+
+#[derive(Debug, Clone)]
+struct TweenSegment<T> {
+    from: T,
+    to: T,
+    frames: u32,
+    easing: Easing,
+}
+
+/// A value which is interpolated over a number of frames.
+#[derive(Debug, Clone)]
+pub struct Tween<T> {
+    /// The segments of the tween.
+    segments: Vec<TweenSegment<T>>,
+    /// The total number of frames in the segments.
+    total_frames: u32,
+    /// The repeat mode of the tween.
+    repeat: TweenRepeat,
+    /// The current frame of the tween.
+    ///
+    /// This is wider than the frame counts so that both directions of a `Yoyo` pass fit.
+    frame: u64,
+}
+
+impl<T> Tween<T>
+where
+    T: Tweenable,
+{
+    /// Create a new tween between two values over a number of frames.
+    pub fn new(from: T, to: T, frames: u32, easing: Easing) -> Self {
+        Tween {
+            segments: vec![TweenSegment {
+                from,
+                to,
+                frames,
+                easing,
+            }],
+            total_frames: frames,
+            repeat: TweenRepeat::Once,
+            frame: 0,
+        }
+    }
+
+    /// Continue the tween from its current end to another value.
+    ///
+    /// Panics if the total number of frames does not fit in a `u32`.
+    pub fn then(mut self, to: T, frames: u32, easing: Easing) -> Self {
+        let from = self.end().clone();
+        self.segments.push(TweenSegment {
+            from,
+            to,
+            frames,
+            easing,
+        });
+        self.total_frames = self
+            .total_frames
+            .checked_add(frames)
+            .expect("the tween has too many frames");
+        self
+    }
+
+    /// Set how the tween behaves once it reaches its end.
+    pub fn repeat(mut self, repeat: TweenRepeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    fn end(&self) -> &T {
+        &self
+            .segments
+            .last()
+            .expect("tweens always have a segment")
+            .to
+    }
+
+    /// The number of frames in one pass through the tween.
+    pub fn frames(&self) -> u32 {
+        self.total_frames
+    }
+
+    /// Advance the tween by one frame.
+    pub fn step(&mut self) {
+        let total_frames = u64::from(self.total_frames);
+        self.frame = match self.repeat {
+            TweenRepeat::Once => (self.frame + 1).min(total_frames),
+            TweenRepeat::Loop => (self.frame + 1) % total_frames.max(1),
+            TweenRepeat::Yoyo => (self.frame + 1) % (2 * total_frames).max(1),
+        };
+    }
+
+    /// Restart the tween from its beginning.
+    pub fn reset(&mut self) {
+        self.frame = 0;
+    }
+
+    /// Whether the tween has finished.
+    ///
+    /// Repeating tweens never finish.
+    pub fn is_done(&self) -> bool {
+        self.repeat == TweenRepeat::Once && self.frame >= u64::from(self.total_frames)
+    }
+
+    /// The current value of the tween.
+    pub fn value(&self) -> T {
+        let total_frames = u64::from(self.total_frames);
+        let position = match self.repeat {
+            TweenRepeat::Yoyo if self.frame > total_frames => 2 * total_frames - self.frame,
+            _ => self.frame,
+        };
+
+        let mut start = 0;
+        for segment in &self.segments {
+            let frames = u64::from(segment.frames);
+            if position < start + frames {
+                let t = ((position - start) as f32) / (frames as f32);
+                return segment.from.lerp(&segment.to, segment.easing.apply(t));
+            }
+            start += frames;
+        }
+
+        self.end().clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Vector2;
+
+    use crate::tween::{Easing, Tween, TweenRepeat, Tweenable};
+
+    const EASINGS: &[Easing] = &[
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+    ];
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} != {}",
+            actual,
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_easing_endpoints() {
+        EASINGS.iter().for_each(|easing| {
+            [(0., 0.), (1., 1.), (-1., 0.), (2., 1.)]
+                .iter()
+                .for_each(|&(t, expected)| {
+                    let actual = easing.apply(t);
+                    assert!(
+                        (actual - expected).abs() < 1e-4,
+                        "{:?}.apply({}): {} != {}",
+                        easing,
+                        t,
+                        actual,
+                        expected,
+                    );
+                })
+        });
+    }
+
+    #[test]
+    fn test_easing_in_out_symmetry() {
+        [
+            (Easing::QuadIn, Easing::QuadOut),
+            (Easing::CubicIn, Easing::CubicOut),
+            (Easing::SineIn, Easing::SineOut),
+            (Easing::BackIn, Easing::BackOut),
+            (Easing::ElasticIn, Easing::ElasticOut),
+            (Easing::BounceIn, Easing::BounceOut),
+        ]
+        .iter()
+        .for_each(|&(ease_in, ease_out)| {
+            (0..=10).map(|i| (i as f32) / 10.).for_each(|t| {
+                assert_close(ease_in.apply(t), 1. - ease_out.apply(1. - t));
+            })
+        });
+
+        [
+            Easing::QuadInOut,
+            Easing::CubicInOut,
+            Easing::SineInOut,
+            Easing::BounceInOut,
+        ]
+        .iter()
+        .for_each(|easing| assert_close(easing.apply(0.5), 0.5));
+    }
+
+    #[test]
+    fn test_easing_shapes() {
+        assert_close(Easing::Linear.apply(0.25), 0.25);
+        assert_close(Easing::QuadIn.apply(0.5), 0.25);
+        assert_close(Easing::CubicIn.apply(0.5), 0.125);
+        assert!(Easing::BackIn.apply(0.2) < 0.);
+        assert!(Easing::BackOut.apply(0.8) > 1.);
+        (0..=20).map(|i| (i as f32) / 20.).for_each(|t| {
+            let bounce = Easing::BounceOut.apply(t);
+            assert!((0. ..=1.).contains(&bounce), "{}", bounce);
+        });
+    }
+
+    #[test]
+    fn test_tweenable() {
+        assert_close(1_f32.lerp(&3., 0.5), 2.);
+        assert_eq!(
+            Vector2::new(0_f32, 2.).lerp(&Vector2::new(2., 0.), 0.5),
+            Vector2::new(1., 1.),
+        );
+        assert_eq!(
+            [0_f32, 0., 1., 1.].lerp(&[1., 0.5, 0., 1.], 0.5),
+            [0.5, 0.25, 0.5, 1.],
+        );
+    }
+
+    fn values<T>(tween: &mut Tween<T>, frames: usize) -> Vec<T>
+    where
+        T: Tweenable,
+    {
+        (0..frames)
+            .map(|_| {
+                let value = tween.value();
+                tween.step();
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tween_once() {
+        let mut tween = Tween::new(0_f32, 4., 4, Easing::Linear);
+
+        assert_eq!(tween.frames(), 4);
+        assert!(!tween.is_done());
+        assert_eq!(values(&mut tween, 6), vec![0., 1., 2., 3., 4., 4.]);
+        assert!(tween.is_done());
+
+        tween.reset();
+        assert!(!tween.is_done());
+        assert_eq!(tween.value(), 0.);
+    }
+
+    #[test]
+    fn test_tween_chain() {
+        let mut tween = Tween::new(0_f32, 2., 2, Easing::Linear)
+            .then(2., 2, Easing::Linear)
+            .then(0., 4, Easing::Linear);
+
+        assert_eq!(tween.frames(), 8);
+        assert_eq!(
+            values(&mut tween, 10),
+            vec![0., 1., 2., 2., 2., 1.5, 1., 0.5, 0., 0.],
+        );
+    }
+
+    #[test]
+    fn test_tween_loop() {
+        let mut tween = Tween::new(0_f32, 3., 3, Easing::Linear).repeat(TweenRepeat::Loop);
+
+        assert_eq!(values(&mut tween, 7), vec![0., 1., 2., 0., 1., 2., 0.]);
+        assert!(!tween.is_done());
+    }
+
+    #[test]
+    fn test_tween_yoyo() {
+        let mut tween = Tween::new(0_f32, 3., 3, Easing::Linear).repeat(TweenRepeat::Yoyo);
+
+        assert_eq!(
+            values(&mut tween, 9),
+            vec![0., 1., 2., 3., 2., 1., 0., 1., 2.],
+        );
+        assert!(!tween.is_done());
+    }
+
+    #[test]
+    fn test_tween_long_yoyo() {
+        let mut tween = Tween::new(0_f32, 1., u32::MAX, Easing::Linear).repeat(TweenRepeat::Yoyo);
+
+        // The frame counter must not overflow when playing backwards.
+        tween.frame = u64::from(u32::MAX);
+        assert_eq!(tween.value(), 1.);
+        tween.step();
+        assert_eq!(tween.frame, u64::from(u32::MAX) + 1);
+        assert!(tween.value() <= 1.);
+        tween.frame = 2 * u64::from(u32::MAX) - 1;
+        tween.step();
+        assert_eq!(tween.frame, 0);
+        assert_eq!(tween.value(), 0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "the tween has too many frames")]
+    fn test_tween_too_long() {
+        Tween::new(0_f32, 1., u32::MAX, Easing::Linear).then(2., 1, Easing::Linear);
+    }
+
+    #[test]
+    fn test_tween_empty() {
+        let mut tween = Tween::new(0_f32, 1., 0, Easing::Linear);
+        assert!(tween.is_done());
+        assert_eq!(values(&mut tween, 2), vec![1., 1.]);
+
+        let mut tween = Tween::new(0_f32, 1., 0, Easing::Linear).repeat(TweenRepeat::Yoyo);
+        assert_eq!(values(&mut tween, 2), vec![1., 1.]);
+    }
+}